@@ -0,0 +1,61 @@
+use std::thread;
+use std::time;
+
+use rules::{Game, GameState, MoveType};
+use serde_json::json;
+use strategy::Strategy;
+
+use crate::{current_timestamp, holochain_call_generator};
+
+/// How long the bot waits before looking for games that need a move again
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(2);
+
+/**
+ * Plays every game of this agent that is waiting for its move, over and over, until the process is stopped.
+ * Games others start against the agent are picked up the same way.
+ */
+pub fn run(url: reqwest::Url, instance: String, mut strategy: Box<dyn Strategy>) -> Result<(), String> {
+	let whoami = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "whoami".into());
+	let games_awaiting_my_move = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "games_awaiting_my_move".into());
+	let get_game = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_game".into());
+	let get_state = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_state".into());
+	let get_legal_moves = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_legal_moves".into());
+	let make_move = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "make_move".into());
+
+	let me = whoami(json!({}))?.as_str().unwrap_or("").to_string();
+	println!("Bot playing as {}. Start a game with this address to play against it.", me);
+
+	loop {
+		let games: Vec<String> = match games_awaiting_my_move(json!({})).and_then(|games| serde_json::from_value(games).map_err(|e| e.to_string())) {
+			Ok(games) => games,
+			Err(e) => {
+				// the conductor may only be restarting, try again next time round
+				println!("Error looking for games: {}", e);
+				Vec::new()
+			}
+		};
+		for game in games {
+			let result = get_game(json!({"game_address": game}))
+				.and_then(|game_entry| serde_json::from_value::<Game>(game_entry).map_err(|e| e.to_string()))
+				.and_then(|game_entry| {
+					let state: GameState = serde_json::from_value(get_state(json!({"game_address": game}))?)
+						.map_err(|e| e.to_string())?;
+					strategy.observe(&game_entry, &state);
+					let legal_moves: Vec<MoveType> = serde_json::from_value(get_legal_moves(json!({"game_address": game}))?)
+						.map_err(|e| e.to_string())?;
+					match strategy.choose(&game_entry, &state, &legal_moves) {
+						Some(move_type) => make_move(json!({"new_move": {
+							"game": game,
+							"move_type": move_type,
+							"timestamp": current_timestamp()
+						}})).map(|_| println!("{}: played {}", game, rules::record::move_text(&move_type))),
+						None => Ok(()),
+					}
+				});
+			if let Err(e) = result {
+				println!("{}: Error: {}", game, e);
+			}
+		}
+		thread::sleep(POLL_INTERVAL);
+	}
+}
@@ -0,0 +1,191 @@
+use serde_json::json;
+use structopt::StructOpt;
+
+use crate::{current_timestamp, holochain_rpc_generator, is_agent_addr, is_hash, parse_game_options, CallError};
+
+/// The command did what was asked
+pub const EXIT_OK: i32 = 0;
+/// The zome refused the call, e.g. an illegal move or an unknown game
+pub const EXIT_REJECTED: i32 = 1;
+/// The arguments were not valid so nothing was sent to the conductor
+pub const EXIT_INVALID_ARGUMENTS: i32 = 2;
+/// The conductor could not be reached or could not make the call, e.g. it is not running the given instance
+pub const EXIT_UNREACHABLE: i32 = 3;
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+	/// Print the agent address of this instance
+	#[structopt(name = "whoami")]
+	Whoami,
+	/// Create a new game and print its address. Options are the same as for new_game in the interactive CLI:
+	/// [private] [<min>..<max>] [swaps=<n>] [consent]
+	#[structopt(name = "new-game")]
+	NewGame {
+		/// Agent address of the opponent
+		opponent: String,
+		options: Vec<String>,
+	},
+	/// Make a move in a game, e.g. move <game_address> '{"Suggest": {"suggestion": 3}}'
+	#[structopt(name = "move")]
+	Move {
+		game: String,
+		/// The move as JSON
+		move_json: String,
+	},
+	/// Print the board of a game
+	#[structopt(name = "render")]
+	Render {
+		game: String,
+	},
+	/// Public proposals to play a game
+	#[structopt(name = "proposals")]
+	Proposals {
+		#[structopt(subcommand)]
+		command: ProposalsCommand,
+	},
+	/// Print the games started in response to a proposal, one per line as <game_address> <agent_address>
+	#[structopt(name = "responses")]
+	Responses {
+		proposal: String,
+	},
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ProposalsCommand {
+	/// Print the current proposals, one per line as <proposal_address> <agent_address> <message>
+	#[structopt(name = "list")]
+	List,
+	/// Publish a proposal and print its address
+	#[structopt(name = "create")]
+	Create {
+		message: Vec<String>,
+	},
+	/// Accept a proposal and print the address of the game it starts
+	#[structopt(name = "accept")]
+	Accept {
+		proposal: String,
+	},
+}
+
+enum Failure {
+	InvalidArguments(String),
+	Rejected(String),
+	Unreachable(String),
+}
+
+impl From<CallError> for Failure {
+	fn from(e: CallError) -> Self {
+		match e {
+			CallError::Conductor(e) => Failure::Unreachable(e),
+			CallError::Zome(e) => Failure::Rejected(e),
+		}
+	}
+}
+
+/**
+ * Runs a single command and returns the status code to exit with.
+ * Results go to stdout with nothing else around them so they can be used in scripts, errors go to stderr.
+ */
+pub fn run(url: reqwest::Url, instance: String, command: Command) -> i32 {
+	let call = |func: &str| holochain_rpc_generator(url.clone(), instance.clone(), "main".into(), func.into());
+
+	let result: Result<(), Failure> = match command {
+		Command::Whoami => {
+			call("whoami")(json!({})).map(|agent_addr| {
+				println!("{}", agent_addr.as_str().unwrap_or(""));
+			}).map_err(Failure::from)
+		},
+		Command::NewGame { opponent, options } => {
+			if !is_agent_addr(&opponent) {
+				Err(Failure::InvalidArguments("the opponent must be a valid agent address".into()))
+			} else {
+				parse_game_options(&options.join(" ")).map_err(Failure::InvalidArguments).and_then(|mut new_game| {
+					new_game["opponent"] = json!(opponent);
+					new_game["timestamp"] = json!(current_timestamp());
+					let game_addr = call("create_game")(new_game)?;
+					println!("{}", game_addr.as_str().unwrap_or(""));
+					Ok(())
+				})
+			}
+		},
+		Command::Move { game, move_json } => {
+			match serde_json::from_str::<serde_json::Value>(&move_json) {
+				Ok(move_type) if is_hash(&game) => {
+					let new_move = json!({
+						"game": game,
+						"move_type": move_type,
+						"timestamp": current_timestamp()
+					});
+					// check the move first so an illegal one is reported with the reason instead of a failed commit
+					call("validate_move")(json!({"new_move": new_move.clone()})).map_err(Failure::from).and_then(|check| {
+						if check["valid"] == true {
+							call("make_move")(json!({"new_move": new_move}))?;
+							Ok(())
+						} else {
+							Err(Failure::Rejected(format!("invalid move: {}", check["reason"].as_str().unwrap_or(""))))
+						}
+					})
+				},
+				Ok(_) => Err(Failure::InvalidArguments("the game must be a valid address".into())),
+				Err(e) => Err(Failure::InvalidArguments(format!("the move must be JSON: {}", e))),
+			}
+		},
+		Command::Render { game } => {
+			if is_hash(&game) {
+				call("render_state")(json!({"game_address": game})).map(|board| {
+					println!("{}", board.as_str().unwrap_or(""));
+				}).map_err(Failure::from)
+			} else {
+				Err(Failure::InvalidArguments("the game must be a valid address".into()))
+			}
+		},
+		Command::Proposals { command: ProposalsCommand::List } => {
+			call("get_proposals")(json!({})).map(|proposals| {
+				proposals.as_array().unwrap_or(&Vec::new()).iter().for_each(|p| {
+					println!("{}\t{}\t{}", p["address"].as_str().unwrap_or(""), p["entry"]["agent"].as_str().unwrap_or(""), p["entry"]["message"].as_str().unwrap_or(""));
+				});
+			}).map_err(Failure::from)
+		},
+		Command::Proposals { command: ProposalsCommand::Create { message } } => {
+			call("create_proposal")(json!({"message": message.join(" ")})).map(|proposal_addr| {
+				println!("{}", proposal_addr.as_str().unwrap_or(""));
+			}).map_err(Failure::from)
+		},
+		Command::Proposals { command: ProposalsCommand::Accept { proposal } } => {
+			if is_hash(&proposal) {
+				call("accept_proposal")(json!({"proposal_addr": proposal, "created_at": current_timestamp()})).map(|game_addr| {
+					println!("{}", game_addr.as_str().unwrap_or(""));
+				}).map_err(Failure::from)
+			} else {
+				Err(Failure::InvalidArguments("the proposal must be a valid address".into()))
+			}
+		},
+		Command::Responses { proposal } => {
+			if is_hash(&proposal) {
+				call("check_responses")(json!({"proposal_addr": proposal})).map(|responses| {
+					responses.as_array().unwrap_or(&Vec::new()).iter().for_each(|r| {
+						println!("{}\t{}", r["address"].as_str().unwrap_or(""), r["entry"]["player_1"].as_str().unwrap_or(""));
+					});
+				}).map_err(Failure::from)
+			} else {
+				Err(Failure::InvalidArguments("the proposal must be a valid address".into()))
+			}
+		},
+	};
+
+	match result {
+		Ok(()) => EXIT_OK,
+		Err(Failure::InvalidArguments(e)) => {
+			eprintln!("Error: {}", e);
+			EXIT_INVALID_ARGUMENTS
+		},
+		Err(Failure::Rejected(e)) => {
+			eprintln!("Error: {}", e);
+			EXIT_REJECTED
+		},
+		Err(Failure::Unreachable(e)) => {
+			eprintln!("Error: {}\nCheck the conductor is running on {} and the instanceId {} is in the conductor config.", e, url, instance);
+			EXIT_UNREACHABLE
+		},
+	}
+}
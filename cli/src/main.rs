@@ -1,27 +1,67 @@
+use std::fmt;
+use std::fs;
 use std::io;
 use std::iter::repeat;
+use std::sync::{Arc, Mutex};
 use std::time::{self, SystemTime, UNIX_EPOCH};
 use std::thread;
 use serde_json::json;
 use structopt::StructOpt;
-use linefeed::{Interface, ReadResult};
+use linefeed::{Interface, ReadResult, Terminal};
+use rules::{GameRecord, GameState, DEFAULT_MAX_SWAPS, GAME_TYPE};
+
+mod bot;
+mod commands;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "example", about = "An example of StructOpt usage.")]
 struct Cli {
 	/// Url to connect to the running conductor HTTP port (default: http://localhost:3000)
-    url: reqwest::Url,
+	#[structopt(required_unless = "replay")]
+    url: Option<reqwest::Url>,
 	/// This is the instance ID in the conductor that is running the game on the given port (e.g gameInstance)
-	instance: String,
-    
+	#[structopt(required_unless = "replay")]
+	instance: Option<String>,
+	/// Url of a conductor websocket interface for the same instance (e.g ws://localhost:3401).
+	/// When given the board is refreshed automatically as soon as the opponent does something.
+	#[structopt(long = "signals")]
+	signals: Option<reqwest::Url>,
+	/// Instead of playing yourself let a bot play every game of this agent, using the given strategy
+	/// (random, greedy, minimax or frequency)
+	#[structopt(long = "bot")]
+	bot: Option<String>,
+	/// Step through a game record saved with "export" instead of connecting to a conductor
+	#[structopt(long = "replay")]
+	replay: Option<String>,
+	/// Make a single call and exit instead of starting the interactive CLI.
+	/// Exits with 1 if the zome refuses the call, 2 for invalid arguments and 3 if the conductor can't be reached.
+	#[structopt(subcommand)]
+	command: Option<commands::Command>,
 }
 
 static COMMANDS: &[(&str, &str)] = &[
     ("help",             "Displays this the help page"),
     ("join_game",        "Set the game to make moves against, usage: join_game <game_address>"),
-    ("new_game",         "Create a new game to play with an opponent, usage: new_game <opponent_address>"),
-    ("moves",            "Display the set of moves this game supports"),
+    ("new_game",         "Create a new game to play with an opponent, usage: new_game <opponent_address> [private] [<min>..<max>] [swaps=<n>] [consent]. Only the two players can see the moves of a private game. Numbers go from 1 to 10 unless a range is given. Each game allows 2 swaps unless swaps=<n> is given, with consent both players must agree to each swap"),
+    ("spectate",         "Follow a game someone else is playing without being able to move, usage: spectate <game_address>"),
+    ("live_games",       "List the games that can be spectated"),
+    ("stats",            "Show your suggestion and prediction record across all your games, or another player's, usage: stats [agent_address]"),
+    ("leaderboard",      "Show the best players, usage: leaderboard [game_type] [limit]. Defaults to the top 10 of suggest_predict"),
+    ("moves",            "Display the moves that can be made in this game right now, with an example of each"),
+    ("legal_moves",      "List every move you could make in this game right now"),
     ("make_move",        "Make a move in this game, usage: make_move <move_json>"),
+    ("history",          "List the moves of this game so far with the score after each of them"),
+    ("show",             "Display this game as it was after a move, usage: show <move_number>. show 0 is the game before anyone moved"),
+    ("say",              "Send a chat message to your opponent in this game, usage: say <message>"),
+    ("export",           "Save a record of this game to a file, usage: export <file>. Files ending in .json get JSON, anything else a PGN style text record"),
+    ("replay",           "Step back and forth through the moves of a game saved with export, usage: replay <file>. This only uses the game rules so it works without the game being on the network"),
+    ("rematch",          "Once a game is over ask your opponent for a rematch, or accept the rematch they asked for"),
+
+    ("create_tournament", "Organise a tournament, usage: create_tournament <round_robin|single_elimination> <player_cap> <name>"),
+    ("register",         "Register to play in a tournament, usage: register <tournament_address>"),
+    ("start_tournament", "Close registration for a tournament you organised, which fixes its pairings, usage: start_tournament <tournament_address>"),
+    ("bracket",          "Show the pairings and results of a tournament, usage: bracket <tournament_address>"),
+    ("report_result",    "Once the current game is over report it as the result of your tournament pairing, usage: report_result <tournament_address>"),
  
     ("create_proposal",  "Publicly publish that you are looking for someone to play with. Usage: post_propoal <message>"),
     ("accept_proposal",   "Accept a propsal. This will start a new game. Usage: accept_proposal <proposal_hash>"),
@@ -33,24 +73,68 @@ static COMMANDS: &[(&str, &str)] = &[
 ];
 
 fn main() -> io::Result<()> {
-    let cli = Cli::from_args();
+    let mut cli = Cli::from_args();
+
+    if let Some(file) = cli.replay.clone() {
+    	let interface = Interface::new("Holochain generic game")?;
+    	if let Err(e) = replay(&interface, &file) {
+    		println!("Error: {}", e);
+    	}
+    	return Ok(());
+    }
+    // both are required when not replaying
+    let url = cli.url.clone().unwrap();
+    let instance = cli.instance.clone().unwrap();
+
+    if let Some(strategy) = cli.bot.clone() {
+    	let result = strategy::named(&strategy).and_then(|strategy| bot::run(url.clone(), instance.clone(), strategy));
+    	if let Err(e) = result {
+    		println!("Error: {}", e);
+    	}
+    	return Ok(());
+    }
+
+    if let Some(command) = cli.command.take() {
+    	std::process::exit(commands::run(url, instance, command));
+    }
 
     // create the functions required for playing the game
-    let whoami = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "whoami".into());
-    let valid_moves = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_valid_moves".into());
-    let make_move = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "make_move".into());
-    let create_game = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "create_game".into());
-    let render_game = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "render_state".into());
+    let whoami = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "whoami".into());
+    let valid_moves = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_valid_moves".into());
+    let legal_moves = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_legal_moves".into());
+    let validate_move = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "validate_move".into());
+    let make_move = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "make_move".into());
+    let create_game = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "create_game".into());
+    let render_game = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "render_state".into());
+    let render_game_at = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "render_state_at".into());
+    let get_state_history = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_state_history".into());
+    let send_chat = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "send_chat".into());
+    let get_chat = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_chat".into());
+    let list_live_games = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "list_live_games".into());
+    let get_player_stats = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_player_stats".into());
+    let get_leaderboard = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_leaderboard".into());
+    let export_game = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "export_game".into());
+    let request_rematch = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "request_rematch".into());
+    let get_rematch_requests = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_rematch_requests".into());
+    let accept_rematch = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "accept_rematch".into());
+    let get_series = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_series".into());
+
+    // tournament funcs
+    let create_tournament = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "create_tournament".into());
+    let register = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "register".into());
+    let start_tournament = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "start_tournament".into());
+    let get_bracket = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_bracket".into());
+    let report_result = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "report_result".into());
 
     // matchmaking funcs
-    let create_proposal = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "create_proposal".into());
-    let get_proposals = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "get_proposals".into());
-    let accept_proposal = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "accept_proposal".into());
-    let check_responses = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "check_responses".into());
-    let _remove_proposal = holochain_call_generator(cli.url.clone(), cli.instance.clone(), "main".into(), "remove_proposal".into());
+    let create_proposal = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "create_proposal".into());
+    let get_proposals = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_proposals".into());
+    let accept_proposal = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "accept_proposal".into());
+    let check_responses = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "check_responses".into());
+    let _remove_proposal = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "remove_proposal".into());
    
 
-    let interface = Interface::new("Holochain generic game")?;
+    let interface = Arc::new(Interface::new("Holochain generic game")?);
 
     println!("");
     println!("");
@@ -63,15 +147,16 @@ fn main() -> io::Result<()> {
     println!("");
     println!("");
 
-    match whoami(json!({})) {
+    let agent_addr = match whoami(json!({})) {
     	Ok(agent_addr) => {
     		println!("Your agent address is {}\n\nSend this to other players so they can invite you to a game.", agent_addr);
+    		agent_addr
     	},
     	Err(_e) => {
-    		println!("No holochain instance named {} running on {}. Check the conductor is running and the instanceId in the conductor config is correct.", cli.instance, cli.url);
+    		println!("No holochain instance named {} running on {}. Check the conductor is running and the instanceId in the conductor config is correct.", instance, url);
     		return Ok(());
     	}
-    }
+    };
 
     println!("");
     println!("");
@@ -79,6 +164,14 @@ fn main() -> io::Result<()> {
 	interface.set_prompt("No game> ")?;
 
 	let mut current_game: Option<String> = None;
+	let shared_game: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+
+	let spectating: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+
+	if let Some(signals_url) = cli.signals.clone() {
+		listen_for_signals(signals_url, url.clone(), instance.clone(), interface.clone(), shared_game.clone());
+	}
+	watch_spectated_game(url.clone(), instance.clone(), interface.clone(), spectating.clone());
 
  	while let ReadResult::Input(line) = interface.read_line()? {
 
@@ -109,51 +202,295 @@ fn main() -> io::Result<()> {
             	}
             }
             "new_game" => {
-            	if is_agent_addr(args) {
-            		let result = create_game(json!({
-            			"opponent": args,
-            			"timestamp": current_timestamp()
-            		}));
+            	let (opponent, options) = split_first_word(args);
+            	if !is_agent_addr(opponent) {
+            		Err("argument must be valid agent address of an opponent.".into())
+            	} else {
+            		let result = parse_game_options(options).and_then(|mut new_game| {
+            			new_game["opponent"] = json!(opponent);
+            			new_game["timestamp"] = json!(current_timestamp());
+            			create_game(new_game)
+            		});
                     result.map(|result| {
                         current_game = result.as_str().map(|s| s.to_string());
                     })
+            	}
+            }
+            "spectate" => {
+            	if is_hash(args) {
+            		println!("Spectating {}. The board will refresh as moves are made.", args);
+            		current_game = None;
+            		*spectating.lock().unwrap() = Some(args.into());
+            		interface.set_prompt(&format!("spectating {}> ", args))?;
+                    Ok(())
             	} else {
-            		Err("argument must be valid agent address of an opponent.".into())
+            		Err("argument must be a valid address".into())
             	}
             }
+            "live_games" => {
+                list_live_games(json!({})).map(|result| {
+                    println!("Live games: \n");
+                    result.as_array().unwrap().iter().for_each(|r| {
+                        println!("[{}] : {{ Player 1: {}, Player 2: {} }}", r["address"].as_str().unwrap(), r["entry"]["player_1"], r["entry"]["player_2"]);
+                    });
+                    println!("use \"spectate\" with any of the listed addresses to watch: \n");
+                })
+            },
+            "stats" => {
+            	let agent = if args.is_empty() { agent_addr.clone() } else { json!(args) };
+            	if args.is_empty() || is_agent_addr(args) {
+            		get_player_stats(json!({"agent": agent})).map(|stats| {
+            			println!("Stats for {} over {} games:\n", agent, stats["games_played"]);
+            			println!("  suggestion: {}/{}", stats["successful_suggestions"], stats["suggestion_attempts"]);
+            			println!("  prediction: {}/{} ({:.0}%)", stats["successful_predictions"], stats["prediction_attempts"], stats["prediction_accuracy"].as_f64().unwrap_or(0.0) * 100.0);
+            			println!("\nPrediction accuracy per game, oldest first:");
+            			stats["accuracy_over_time"].as_array().unwrap().iter().for_each(|g| {
+            				println!("  {:>4.0}%  {}", g["accuracy"].as_f64().unwrap_or(0.0) * 100.0, g["game"].as_str().unwrap_or(""));
+            			});
+            			let favourites: Vec<String> = stats["favourite_suggestions"].as_array().unwrap().iter().take(3)
+            				.map(|f| format!("{} ({} times)", f["suggestion"], f["count"]))
+            				.collect();
+            			println!("\nFavourite suggestions: {}\n", favourites.join(", "));
+            		})
+            	} else {
+            		Err("argument must be valid agent address".into())
+            	}
+            },
+            "leaderboard" => {
+            	let (game_type, limit) = split_first_word(args);
+            	let game_type = if game_type.is_empty() { GAME_TYPE } else { game_type };
+            	match limit.parse::<usize>().or_else(|e| if limit.is_empty() { Ok(10) } else { Err(e) }) {
+            		Ok(limit) => get_leaderboard(json!({"game_type": game_type, "limit": limit})).map(|standings| {
+            			println!("Leaderboard for {}: \n", game_type);
+            			println!("     {:>4} {:>6} {:>5} {:>10}  Agent", "Wins", "Losses", "Draws", "Prediction");
+            			standings.as_array().unwrap().iter().enumerate().for_each(|(i, s)| {
+            				let you = if s["agent"] == agent_addr { " (you)" } else { "" };
+            				println!("{:>3}. {:>4} {:>6} {:>5} {:>9.0}%  {}{}", i + 1, s["wins"].as_u64().unwrap_or(0), s["losses"].as_u64().unwrap_or(0), s["draws"].as_u64().unwrap_or(0), s["prediction_rate"].as_f64().unwrap_or(0.0) * 100.0, s["agent"].as_str().unwrap_or(""), you);
+            			});
+            			println!();
+            		}),
+            		Err(_) => Err("usage: leaderboard [game_type] [limit]".into()),
+            	}
+            },
             "moves" => {
-            	valid_moves(json!({})).map(|result| {
-	            	println!("The valid moves are:");
-	            	result.as_array().unwrap()
-	            	.iter()
-	            	.for_each(|elem| {
-	            		println!("- {}", elem);
-	            	});
-                    println!();
-            	})
+            	if let Some(current_game) = current_game.clone() {
+	            	valid_moves(json!({"game_address": current_game})).map(|result| {
+		            	println!("The moves that can be made now are:");
+		            	result.as_array().unwrap()
+		            	.iter()
+		            	.for_each(print_move_schema);
+	                    println!();
+	            	})
+            	} else {
+            		Err("No game set. use the \"join_game\" command.".into())
+            	}
+            },
+            "legal_moves" => {
+            	if let Some(current_game) = current_game.clone() {
+            		legal_moves(json!({"game_address": current_game})).map(|result| {
+            			let moves: Vec<String> = result.as_array().unwrap().iter().map(|legal_move| legal_move.to_string()).collect();
+            			if moves.is_empty() {
+            				println!("There are no moves to make right now.\n");
+            			} else {
+            				println!("The legal moves are:\n{}\n", moves.join("\n"));
+            			}
+            		})
+            	} else {
+            		Err("No game set. use the \"join_game\" command.".into())
+            	}
             },
             "make_move" => {
             	if let Some(current_game) = current_game.clone() {
             		let move_json: serde_json::Value = serde_json::from_str(args).unwrap_or(serde_json::Value::Null);
-	            	println!("making move: {:?}", args);
-	            	make_move(json!({
-		            	"new_move": {
-		            		"game": current_game,
-		            		"move_type": move_json,
-		            		"timestamp": current_timestamp()
-		            	}
-	            	})).map(|_| {
+            		let new_move = json!({
+	            		"game": current_game,
+	            		"move_type": move_json,
+	            		"timestamp": current_timestamp()
+	            	});
+	            	// check the move first so a mistake is reported straight away rather than as a failed commit
+	            	let parsed: Result<(), String> = if move_json.is_null() { Err("the move must be JSON, use the \"moves\" command to see examples".into()) } else { Ok(()) };
+	            	parsed.and_then(|_| validate_move(json!({"new_move": new_move.clone()}))).and_then(|check| {
+	            		if check["valid"] == true {
+	            			Ok(())
+	            		} else {
+	            			let legal: Vec<String> = check["legal_moves"].as_array().map_or(Vec::new(), |moves| moves.iter().map(|m| m.to_string()).collect());
+	            			if !legal.is_empty() {
+	            				println!("You could play one of:\n{}", legal.join("\n"));
+	            			}
+	            			Err(format!("invalid move: {}", check["reason"].as_str().unwrap_or("")))
+	            		}
+	            	}).and_then(|_| {
+	            		println!("making move: {:?}", args);
+	            		make_move(json!({"new_move": new_move}))
+	            	}).map(|_| {
                         println!("Move cast successfully");
-                        println!("Waiting for gossip...");
-                        // wait a bit so it displays correctly
-                        thread::sleep(time::Duration::from_millis(4000));
-                        println!("OK!")
+                        if cli.signals.is_none() {
+                            println!("Waiting for gossip...");
+                            // without signals there is no way to know when the move arrives so wait a bit
+                            thread::sleep(time::Duration::from_millis(4000));
+                            println!("OK!")
+                        }
                     })
                 }
             	else {
             		Err("No game set to make moves on. use the \"join_game\" command.".into())
             	}
             },
+            "history" => {
+            	if let Some(current_game) = current_game.clone() {
+            		get_state_history(json!({"game_address": current_game})).and_then(|history| {
+            			serde_json::from_value::<Vec<GameState>>(history).map_err(|e| e.to_string())
+            		}).map(|history| {
+            			let scores = |state: &GameState| format!("player 1 {} - {} player 2", state.player_1_score(), state.player_2_score());
+            			println!("History:\n");
+            			println!("  0. start ({})", scores(&history[0]));
+            			for (i, state) in history.iter().enumerate().skip(1) {
+            				let game_move = &state.moves[i - 1];
+            				let player = if json!(game_move.author) == agent_addr { "you" } else { "opponent" };
+            				println!("{:>3}. {} played {} ({})", i, player, rules::record::move_text(&game_move.move_type), scores(state));
+            			}
+            			println!("\nuse \"show\" with any of the move numbers to see the game at that point.\n");
+            		})
+            	} else {
+            		Err("No game set. use the \"join_game\" command.".into())
+            	}
+            },
+            "show" => {
+            	match (current_game.clone(), args.parse::<usize>()) {
+            		(Some(current_game), Ok(move_index)) => render_game_at(json!({"game_address": current_game, "move_index": move_index})).map(|render_result| {
+            			println!("After move {}:\n{}", move_index, render_result.as_str().unwrap_or(""));
+            			println!("The game as it is now:");
+            		}),
+            		(Some(_), Err(_)) => Err("usage: show <move_number>".into()),
+            		(None, _) => Err("No game set. use the \"join_game\" command.".into()),
+            	}
+            },
+            "say" => {
+            	if let Some(current_game) = current_game.clone() {
+            		send_chat(json!({
+            			"game_address": current_game,
+            			"message": args,
+            			"timestamp": current_timestamp()
+            		})).map(|_| ())
+            	} else {
+            		Err("No game set to chat in. use the \"join_game\" command.".into())
+            	}
+            },
+            "export" => {
+            	match current_game.clone() {
+            		Some(_) if args.is_empty() => Err("usage: export <file>".into()),
+            		Some(game) => {
+            			let format = if args.ends_with(".json") { "Json" } else { "Pgn" };
+            			export_game(json!({"game_address": game, "format": format})).and_then(|record| {
+            				let record = record.as_str().ok_or_else(|| format!("The game record is not text: {}", record))?;
+            				fs::write(args, record).map_err(|e| e.to_string())
+            			}).map(|_| {
+            				println!("Game saved to {}\n", args);
+            			})
+            		},
+            		None => Err("No game set to export. use the \"join_game\" command.".into()),
+            	}
+            },
+            "replay" => {
+            	if args.is_empty() {
+            		Err("usage: replay <file>".into())
+            	} else {
+            		let result = replay(&interface, args);
+            		let prompt = match spectating.lock().unwrap().clone() {
+            			Some(game) => format!("spectating {}> ", game),
+            			None => "No game> ".to_string(),
+            		};
+            		interface.set_prompt(&prompt)?;
+            		result
+            	}
+            },
+            "rematch" => {
+            	if let Some(game) = current_game.clone() {
+            		get_rematch_requests(json!({"game_address": game})).and_then(|requests| {
+            			let opponent_request = requests.as_array().unwrap().iter()
+            				.find(|r| r["entry"]["requester"] != agent_addr)
+            				.map(|r| r["address"].clone());
+            			match opponent_request {
+            				Some(request_addr) => accept_rematch(json!({"request_addr": request_addr, "created_at": current_timestamp()})).map(|game_addr| {
+            					println!("Rematch accepted. Game created with address: {}", game_addr);
+            					current_game = Some(game_addr.as_str().unwrap().into());
+            				}),
+            				None => request_rematch(json!({"game_address": game, "created_at": current_timestamp()})).map(|_| {
+            					println!("Rematch requested. The new game will start once your opponent accepts.");
+            				}),
+            			}
+            		}).and_then(|_| {
+            			let series_game = current_game.clone().unwrap_or(game);
+            			get_series(json!({"game_address": series_game})).map(|series| {
+            				let (my_wins, their_wins) = if series["player_1"] == agent_addr {
+            					(&series["player_1_wins"], &series["player_2_wins"])
+            				} else {
+            					(&series["player_2_wins"], &series["player_1_wins"])
+            				};
+            				println!("Series score: you {} - {} opponent ({} drawn)\n", my_wins, their_wins, series["draws"]);
+            			})
+            		})
+            	} else {
+            		Err("No game set. use the \"join_game\" command.".into())
+            	}
+            },
+            "create_tournament" => {
+            	let (format, rest) = split_first_word(args);
+            	let (player_cap, name) = split_first_word(rest);
+            	let format = match format {
+            		"round_robin" => Some("RoundRobin"),
+            		"single_elimination" => Some("SingleElimination"),
+            		_ => None,
+            	};
+            	match (format, player_cap.parse::<usize>()) {
+            		(Some(format), Ok(player_cap)) => create_tournament(json!({
+            			"name": name,
+            			"format": format,
+            			"player_cap": player_cap,
+            			"created_at": current_timestamp()
+            		})).map(|tournament_addr| {
+            			println!("Tournament created with address: {}", tournament_addr);
+            			println!("Send this to other players so they can \"register\" for it.\n");
+            		}),
+            		_ => Err("usage: create_tournament <round_robin|single_elimination> <player_cap> <name>".into()),
+            	}
+            },
+            "register" => {
+            	if is_hash(args) {
+            		register(json!({"tournament_address": args})).map(|_| {
+            			println!("Registered. Use \"bracket {}\" to see your pairings once it starts.\n", args);
+            		})
+            	} else {
+            		Err("argument must be a valid address".into())
+            	}
+            },
+            "start_tournament" => {
+            	if is_hash(args) {
+            		start_tournament(json!({"tournament_address": args, "started_at": current_timestamp()})).and_then(|_| {
+            			println!("Tournament started, nobody else can register.");
+            			get_bracket(json!({"tournament_address": args})).map(|bracket| print_bracket(&bracket))
+            		})
+            	} else {
+            		Err("argument must be a valid address".into())
+            	}
+            },
+            "bracket" => {
+            	if is_hash(args) {
+            		get_bracket(json!({"tournament_address": args})).map(|bracket| print_bracket(&bracket))
+            	} else {
+            		Err("argument must be a valid address".into())
+            	}
+            },
+            "report_result" => {
+            	match current_game.clone() {
+            		Some(game) if is_hash(args) => report_result(json!({"tournament_address": args, "game_address": game})).and_then(|_| {
+            			println!("Result reported.");
+            			get_bracket(json!({"tournament_address": args})).map(|bracket| print_bracket(&bracket))
+            		}),
+            		Some(_) => Err("argument must be a valid address".into()),
+            		None => Err("No game set to report. use the \"join_game\" command.".into()),
+            	}
+            },
             "create_proposal" => {
                 println!("creating proposal with message {:?}", args);
                 let result = create_proposal(json!({"message": args}));
@@ -209,6 +546,9 @@ fn main() -> io::Result<()> {
 
 		if let Some(current_game_string) = current_game.clone() {
  			interface.set_prompt(&format!("{}> ", current_game_string))?;
+ 			if let Ok(chat) = get_chat(json!({"game_address": current_game_string.clone(), "since": 0})) {
+ 				print_recent_chat(&chat, &agent_addr);
+ 			}
  			match render_game(json!({"game_address": current_game_string.clone()})) {
  				Ok(render_result) => {
             		println!("{}", render_result.as_str().unwrap());
@@ -219,6 +559,10 @@ fn main() -> io::Result<()> {
  				}
  			}
  		}
+		*shared_game.lock().unwrap() = current_game.clone();
+		if current_game.is_some() {
+			*spectating.lock().unwrap() = None;
+		}
 	}
     Ok(())
 }
@@ -232,7 +576,38 @@ fn holochain_call_generator(
 	instance: String,
 	zome: String,
 	func: String,
-) -> Box<Fn(serde_json::Value) -> Result<serde_json::Value, String>> {
+) -> Box<dyn Fn(serde_json::Value) -> Result<serde_json::Value, String>> {
+	let call = holochain_rpc_generator(url, instance, zome, func);
+	Box::new(move |params| call(params).map_err(|e| e.to_string()))
+}
+
+/// Why a call to a zome function failed
+#[derive(Debug)]
+enum CallError {
+	/// The conductor could not be reached or could not make the call, e.g. there is no instance with the given name
+	Conductor(String),
+	/// The zome function ran and returned an error
+	Zome(String),
+}
+
+impl fmt::Display for CallError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CallError::Conductor(e) | CallError::Zome(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+/**
+ * Like holochain_call_generator but the functions tell the conductor failing to make a call apart from the zome
+ * returning an error
+ */
+fn holochain_rpc_generator(
+	url: reqwest::Url,
+	instance: String,
+	zome: String,
+	func: String,
+) -> Box<dyn Fn(serde_json::Value) -> Result<serde_json::Value, CallError>> {
 
 	let client = reqwest::Client::new();
 
@@ -251,32 +626,308 @@ fn holochain_call_generator(
 	};
 
 	Box::new(move |params| {
-		let call_result: serde_json::Value = client.post(url.clone())
+		let response: serde_json::Value = client.post(url.clone())
 		    .json(&make_rpc_call(params))
-		    .send().map_err(|e| e.to_string())?
-		    .json()
-		    .map(|r: serde_json::Value| {
-		    	r["result"].clone()
-		    })
-		    .map(|s| serde_json::from_str(
-                s.as_str().expect(&format!("Holochain did not return a string result: {}", s))
-            ).expect(&format!("Holochain did not return a valid stringified JSON result: {}", s)))
-		    .map_err(|e| e.to_string())?;
+		    .send()
+		    .and_then(|mut response| response.json())
+		    .map_err(|e| CallError::Conductor(e.to_string()))?;
+
+		// the conductor answers with a JSON-RPC error when it can't make the call, e.g. for an unknown instance
+		if !response["error"].is_null() {
+			let error = &response["error"];
+			return Err(CallError::Conductor(error["message"].as_str().map_or_else(|| error.to_string(), |message| message.to_string())));
+		}
+		let call_result: serde_json::Value = response["result"].as_str()
+		    .ok_or_else(|| CallError::Conductor(format!("Holochain did not return a string result: {}", response["result"])))
+		    .and_then(|result| serde_json::from_str(result)
+		        .map_err(|_| CallError::Conductor(format!("Holochain did not return a valid stringified JSON result: {}", result))))?;
 
 		// deal with the json encoded holochain error responses
 		if let Some(inner_result) = call_result.get("Ok") {
 			Ok(inner_result.clone())
 		} else {
-			Err(call_result["Err"].to_string())
+			Err(CallError::Zome(call_result["Err"].to_string()))
 		}
 	})
 
 }
 
+/**
+ * Connects to the conductor websocket interface and prints the signals the zome emits for our instance.
+ * If a signal is about the game currently being played the board is rendered again.
+ */
+// the handler closure has to return ws::Result, however large its error is
+#[allow(clippy::result_large_err)]
+fn listen_for_signals(
+	signals_url: reqwest::Url,
+	url: reqwest::Url,
+	instance: String,
+	interface: Arc<Interface<linefeed::DefaultTerminal>>,
+	current_game: Arc<Mutex<Option<String>>>,
+) {
+	thread::spawn(move || {
+		let result = ws::connect(signals_url.to_string(), |_out| {
+			let render_game = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "render_state".into());
+			let (interface, current_game, instance) = (interface.clone(), current_game.clone(), instance.clone());
+			move |msg: ws::Message| {
+				let signal: serde_json::Value = match msg.as_text().ok().and_then(|text| serde_json::from_str(text).ok()) {
+					Some(signal) => signal,
+					None => return Ok(()),
+				};
+				if signal["instance_id"] != json!(instance) || signal["signal"]["signal_type"] != "User" {
+					return Ok(());
+				}
+				let name = signal["signal"]["name"].as_str().unwrap_or("").to_string();
+				// signal arguments arrive as a stringified JSON
+				let arguments: serde_json::Value = match &signal["signal"]["arguments"] {
+					serde_json::Value::String(s) => serde_json::from_str(s).unwrap_or(serde_json::Value::Null),
+					other => other.clone(),
+				};
+				let message = arguments.get(signal_variant(&name)).cloned().unwrap_or(serde_json::Value::Null);
+
+				let mut w = interface.lock_writer_erase().map_err(|e| ws::Error::new(ws::ErrorKind::Internal, e.to_string()))?;
+				match name.as_str() {
+					"game_invitation" => writeln!(w, "\n{} has started a game with you. Use \"join_game {}\" to play.", message["from"], message["game"].as_str().unwrap_or("")),
+					"proposal_accepted" => writeln!(w, "\nYour proposal was accepted. Use \"join_game {}\" to play.", message["game"].as_str().unwrap_or("")),
+					"move_made" => writeln!(w, "\nYour opponent made a move."),
+					"rematch_requested" => writeln!(w, "\nYour opponent wants a rematch of {}. Use \"rematch\" in that game to accept.", message["game"].as_str().unwrap_or("")),
+					"chat_received" => writeln!(w, "\nopponent: {}", message["chat_message"]["message"].as_str().unwrap_or("")),
+					_ => writeln!(w, "\nReceived signal {}: {}", name, arguments),
+				}.ok();
+
+				let current = current_game.lock().unwrap().clone();
+				if let Some(current) = current {
+					if message["game"].as_str() == Some(current.as_str()) {
+						if let Ok(render_result) = render_game(json!({"game_address": current})) {
+							writeln!(w, "{}", render_result.as_str().unwrap_or("")).ok();
+						}
+					}
+				}
+				Ok(())
+			}
+		});
+		if let Err(e) = result {
+			println!("Lost connection to the signal interface: {}", e);
+		}
+	});
+}
+
+/// How often the board of a spectated game is refreshed
+const SPECTATE_REFRESH_MS: u64 = 2000;
+
+/**
+ * Polls the game being spectated (if any) and prints the moves and board whenever a new move is made.
+ * Spectators are not players so they do not receive signals from the players' zomes.
+ */
+fn watch_spectated_game(
+	url: reqwest::Url,
+	instance: String,
+	interface: Arc<Interface<linefeed::DefaultTerminal>>,
+	spectating: Arc<Mutex<Option<String>>>,
+) {
+	thread::spawn(move || {
+		let get_state = holochain_call_generator(url.clone(), instance.clone(), "main".into(), "get_state".into());
+		let render_game = holochain_call_generator(url, instance, "main".into(), "render_state".into());
+		// the game we last printed and how many of its moves have been shown
+		let mut shown: Option<(String, usize)> = None;
+		loop {
+			let game = spectating.lock().unwrap().clone();
+			if let Some(game) = game {
+				if let Ok(state) = get_state(json!({"game_address": game})) {
+					let moves = state["moves"].as_array().cloned().unwrap_or_default();
+					let (is_new_game, already_shown) = match &shown {
+						Some((shown_game, count)) if *shown_game == game => (false, *count),
+						_ => (true, 0),
+					};
+					if is_new_game || already_shown < moves.len() {
+						if let Ok(mut w) = interface.lock_writer_erase() {
+							moves.iter().enumerate().skip(already_shown).for_each(|(i, game_move)| {
+								writeln!(w, "{}. {} played {}", i + 1, game_move["author"].as_str().unwrap_or(""), game_move["move_type"]).ok();
+							});
+							if let Ok(render_result) = render_game(json!({"game_address": game})) {
+								writeln!(w, "{}", render_result.as_str().unwrap_or("")).ok();
+							}
+						}
+						shown = Some((game, moves.len()));
+					}
+				}
+			} else {
+				shown = None;
+			}
+			thread::sleep(time::Duration::from_millis(SPECTATE_REFRESH_MS));
+		}
+	});
+}
+
 /*===============================
 =            Helpers            =
 ===============================*/
 
+/// How many chat messages to show above the board
+const RECENT_CHAT_MESSAGES: usize = 5;
+
+/// The latest chat messages of a game, oldest first
+fn print_recent_chat(chat: &serde_json::Value, agent_addr: &serde_json::Value) {
+	let messages = match chat.as_array() {
+		Some(messages) if !messages.is_empty() => messages,
+		_ => return,
+	};
+	let skip = messages.len().saturating_sub(RECENT_CHAT_MESSAGES);
+	messages.iter().skip(skip).for_each(|chat_message| {
+		let author = if chat_message["author"] == *agent_addr { "you" } else { "opponent" };
+		println!("{}: {}", author, chat_message["message"].as_str().unwrap_or(""));
+	});
+	println!();
+}
+
+/// One kind of move from get_valid_moves, with its fields and an example to copy into make_move
+fn print_move_schema(schema: &serde_json::Value) {
+	println!("- {}: {}", schema["kind"].as_str().unwrap_or(""), schema["description"].as_str().unwrap_or(""));
+	if let Some(properties) = schema["properties"].as_object() {
+		for (name, field) in properties {
+			let range = match (field["minimum"].as_u64(), field["maximum"].as_u64()) {
+				(Some(min), Some(max)) => format!(" from {} to {}", min, max),
+				(Some(min), None) => format!(" of at least {}", min),
+				_ => String::new(),
+			};
+			println!("    {}: {}{} - {}", name, field["type"].as_str().unwrap_or(""), range, field["description"].as_str().unwrap_or(""));
+		}
+	}
+	println!("    e.g. make_move {}", schema["example"]);
+}
+
+/// The pairings of each round of a tournament, then the standings and the winner once there is one
+fn print_bracket(bracket: &serde_json::Value) {
+	let tournament = &bracket["tournament"];
+	println!("{} ({}, {} of {} players registered)\n", tournament["name"].as_str().unwrap_or(""), tournament["format"].as_str().unwrap_or(""), bracket["players"].as_array().map_or(0, |players| players.len()), tournament["player_cap"]);
+	if bracket["started"] != true {
+		println!("Waiting for the organiser to start it. The pairings are made once it starts.\n");
+	}
+	for (i, round) in bracket["rounds"].as_array().unwrap().iter().enumerate() {
+		let marker = if bracket["current_round"] == json!(i) { " <- current" } else { "" };
+		println!("Round {}{}", i + 1, marker);
+		for pairing in round.as_array().unwrap() {
+			let result = match &pairing["result"] {
+				serde_json::Value::Null => "to play".to_string(),
+				result if result == "Draw" => format!("draw in {}, replay to decide", pairing["game"]),
+				result => format!("won by {}", result["Winner"]),
+			};
+			if pairing["player_2"].is_null() {
+				println!("  {} has a bye", pairing["player_1"]);
+			} else {
+				println!("  {} vs {} : {}", pairing["player_1"], pairing["player_2"], result);
+			}
+		}
+	}
+	println!("\nStandings (wins / draws / losses):");
+	for standing in bracket["standings"].as_array().unwrap() {
+		println!("  {} : {} / {} / {}", standing["player"], standing["wins"], standing["draws"], standing["losses"]);
+	}
+	if !bracket["winner"].is_null() {
+		println!("\nWinner: {}", bracket["winner"]);
+	}
+	println!();
+}
+
+/// Play through a game record with the rules alone, showing the board after each move.
+/// Moves the rules would not have allowed and a result that differs from the recorded one are pointed out.
+fn replay<T: Terminal>(interface: &Interface<T>, file: &str) -> Result<(), String> {
+	let record = fs::read_to_string(file).map_err(|e| e.to_string()).and_then(|text| GameRecord::parse(&text))?;
+	let game = &record.game;
+	let total = record.moves.len();
+
+	// the state before any move, then the state after each one
+	let mut states = vec![GameState::initial()];
+	let mut rule_breaks = vec![None];
+	for game_move in &record.moves {
+		let state = states.last().unwrap().clone();
+		rule_breaks.push(game_move.is_valid(game.clone(), state.clone()).err());
+		states.push(state.evolve(game.clone(), game_move));
+	}
+	let replayed_result = states.last().unwrap().result(game);
+	if replayed_result != record.result {
+		println!("Warning: the record gives the result {:?} but replaying the moves gives {:?}\n", record.result, replayed_result);
+	}
+
+	println!("Replaying {} ({} moves). Enter \"next\" (or nothing), \"back\", \"first\", \"last\", a move number or \"quit\".\n", record.address, total);
+	let mut position = 0;
+	loop {
+		if position > 0 {
+			let game_move = &record.moves[position - 1];
+			let player = if game_move.author == game.player_1 { "player 1" } else { "player 2" };
+			println!("Move {}: {} plays {}", position, player, rules::record::move_text(&game_move.move_type));
+			if let Some(reason) = &rule_breaks[position] {
+				println!("This move breaks the rules: {}", reason);
+			}
+		}
+		println!("{}", states[position].render(game));
+
+		interface.set_prompt(&format!("replay {}/{}> ", position, total)).map_err(|e| e.to_string())?;
+		let line = match interface.read_line().map_err(|e| e.to_string())? {
+			ReadResult::Input(line) => line,
+			_ => break,
+		};
+		position = match line.trim() {
+			"" | "next" => if position < total { position + 1 } else { println!("That was the last move."); position },
+			"back" => if position > 0 { position - 1 } else { println!("No moves have been made yet."); position },
+			"first" => 0,
+			"last" => total,
+			"quit" => break,
+			n => match n.parse::<usize>() {
+				Ok(n) if n <= total => n,
+				_ => { println!("Unknown replay command, the moves go from 0 to {}", total); position },
+			},
+		};
+	}
+	Ok(())
+}
+
+/// The DirectMessage variant name the zome uses for each signal
+fn signal_variant(signal_name: &str) -> &'static str {
+	match signal_name {
+		"game_invitation" => "GameInvitation",
+		"proposal_accepted" => "ProposalAccepted",
+		"move_made" => "MoveMade",
+		"chat_received" => "ChatReceived",
+		"rematch_requested" => "RematchRequested",
+		_ => "",
+	}
+}
+
+/// Parse the options of new_game into the arguments of create_game
+fn parse_game_options(options: &str) -> Result<serde_json::Value, String> {
+	let mut private = false;
+	let mut suggestion_range = serde_json::Value::Null;
+	let mut max_swaps = None;
+	let mut require_consent = false;
+	for option in options.split_whitespace() {
+		if option == "private" {
+			private = true;
+		} else if option == "consent" {
+			require_consent = true;
+		} else if let Some(swaps) = option.strip_prefix("swaps=") {
+			max_swaps = Some(swaps.parse::<usize>().map_err(|_| "swaps must be a number, e.g. swaps=3".to_string())?);
+		} else {
+			let (min, max) = parse_range(option).ok_or_else(|| format!("unknown option {}, the range of numbers must look like 1..10", option))?;
+			suggestion_range = json!({"min": min, "max": max});
+		}
+	}
+	let swap_rules = if max_swaps.is_some() || require_consent {
+		json!({"max_swaps": max_swaps.unwrap_or(DEFAULT_MAX_SWAPS), "require_consent": require_consent})
+	} else {
+		serde_json::Value::Null
+	};
+	Ok(json!({"private": private, "suggestion_range": suggestion_range, "swap_rules": swap_rules}))
+}
+
+/// Parse a range of numbers written as min..max
+fn parse_range(s: &str) -> Option<(usize, usize)> {
+	let mut bounds = s.splitn(2, "..");
+	let min = bounds.next()?.parse().ok()?;
+	let max = bounds.next()?.parse().ok()?;
+	if min <= max { Some((min, max)) } else { None }
+}
+
 fn split_first_word(s: &str) -> (&str, &str) {
     let s = s.trim();
 
@@ -0,0 +1,41 @@
+#[cfg(feature = "holochain")]
+use hdk::holochain_json_api::{error::JsonError, json::JsonString};
+
+use crate::{Address, SuggestionRange, SwapRules};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "holochain", derive(DefaultJson))]
+pub struct Game {
+    pub player_1: Address,
+    pub player_2: Address,
+    pub created_at: u32,
+    /// The moves of a private game are only revealed to its two players
+    #[serde(default)]
+    pub private: bool,
+    /// The finished game this is a rematch of, with the players swapped
+    #[serde(default)]
+    pub rematch_of: Option<Address>,
+    /// The match this game is played in, if any, as the address of the first version of the match entry
+    #[serde(default)]
+    pub in_match: Option<Address>,
+    /// The numbers that can be suggested and predicted. Games created before this was added have None and accept any number
+    #[serde(default)]
+    pub suggestion_range: Option<SuggestionRange>,
+    /// How often and how the players can swap roles. Games created before this was added have None and can swap without limit
+    #[serde(default)]
+    pub swap_rules: Option<SwapRules>,
+    /// How many rounds the game is played over. Games created before this was added have None and never end
+    #[serde(default)]
+    pub rounds: Option<usize>,
+}
+
+impl Game {
+    /// The other player in this game from the point of view of the given player
+    pub fn opponent_of(&self, player: &Address) -> Address {
+        if *player == self.player_1 {
+            self.player_2.clone()
+        } else {
+            self.player_1.clone()
+        }
+    }
+}
@@ -0,0 +1,14 @@
+#[cfg(feature = "holochain")]
+use hdk::holochain_json_api::{error::JsonError, json::JsonString};
+
+use crate::{Address, MoveType};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "holochain", derive(DefaultJson))]
+pub struct Move {
+    pub game: Address,
+    pub author: Address,
+    pub move_type: MoveType,
+    pub previous_move: Address,
+    pub timestamp: u32,
+}
@@ -0,0 +1,40 @@
+#![cfg_attr(feature = "holochain", feature(try_from))]
+// The zome builds this crate with the 2019 nightly its HDK needs, which predates matches! and Option::is_some_and
+#![allow(clippy::match_like_matches_macro, clippy::unnecessary_map_or)]
+/**
+ * The rules of the game, with nothing that needs a conductor. The zome uses them (with the holochain feature) to
+ * validate and reduce moves, and tools like the CLI can use them to play through a game offline.
+ *
+ * All of this code is specific to the game. By changing the moves, state, reducer and validation rules you can
+ * implement you own game.
+ */
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "holochain")]
+#[macro_use]
+extern crate holochain_json_derive;
+
+pub mod game;
+pub mod game_move;
+pub mod moves;
+pub mod record;
+pub mod state;
+pub mod validation;
+
+pub use self::{
+    game::Game,
+    game_move::Move,
+    moves::{MoveSchema, MoveType, SuggestionRange, SwapRules, DEFAULT_MAX_SWAPS, MAX_RANGE_WIDTH},
+    record::GameRecord,
+    state::{GameResult, GameState, ROUNDS_PER_GAME},
+};
+
+#[cfg(feature = "holochain")]
+pub use hdk::holochain_persistence_api::cas::content::Address;
+
+/// Outside of holochain an address is just the string it is written as
+#[cfg(not(feature = "holochain"))]
+pub type Address = String;
+
+/// The name this game is listed under, e.g. on the leaderboard
+pub const GAME_TYPE: &str = "suggest_predict";
@@ -0,0 +1,159 @@
+#[cfg(feature = "holochain")]
+use hdk::holochain_json_api::{error::JsonError, json::JsonString};
+
+use std::collections::BTreeMap;
+
+use crate::{Address, Game, GameState};
+
+/**
+ *
+ * The MoveType enum defines all the types of moves that are valid in your game and the
+ * data they carry. In Checkers you can move a piece (MovePiece) from a location to another location.
+ *
+ */
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "holochain", derive(DefaultJson))]
+pub enum MoveType {
+    // <<DEVCAMP-TODO>> YOUR MOVE ENUM VARIENTS HERE
+    Suggest { suggestion: usize },
+    Predict { prediction: usize },
+    Swap {},
+    RefuseSwap {},
+}
+
+/// The numbers that can be suggested and predicted in a game, inclusive at both ends
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SuggestionRange {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Default for SuggestionRange {
+    fn default() -> Self {
+        SuggestionRange { min: 1, max: 10 }
+    }
+}
+
+/// The furthest apart the smallest and largest number of a game can be, so the legal moves can always be listed
+pub const MAX_RANGE_WIDTH: usize = 100;
+
+impl SuggestionRange {
+    pub fn contains(&self, number: usize) -> bool {
+        self.min <= number && number <= self.max
+    }
+}
+
+/// The number of swaps a game allows unless it is created with other swap rules
+pub const DEFAULT_MAX_SWAPS: usize = 2;
+
+/// When the players can switch between suggesting and predicting. A swap can only be made between rounds by the
+/// player about to suggest. If consent is required it is only a proposal until the opponent answers with a swap
+/// of their own, or turns it down with RefuseSwap.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SwapRules {
+    pub max_swaps: usize,
+    pub require_consent: bool,
+}
+
+impl Default for SwapRules {
+    fn default() -> Self {
+        SwapRules {
+            max_swaps: DEFAULT_MAX_SWAPS,
+            require_consent: false,
+        }
+    }
+}
+
+/// A JSON Schema style description of one field of a move
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FieldSchema {
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub minimum: Option<usize>,
+    pub maximum: Option<usize>,
+    pub description: String,
+}
+
+/// Everything a UI needs to build a form for one kind of move. `kind` is the key to use in the move_type JSON
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveSchema {
+    pub kind: String,
+    pub description: String,
+    pub properties: BTreeMap<String, FieldSchema>,
+    pub required: Vec<String>,
+    pub example: MoveType,
+}
+
+impl MoveType {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MoveType::Suggest { .. } => "Suggest",
+            MoveType::Predict { .. } => "Predict",
+            MoveType::Swap {} => "Swap",
+            MoveType::RefuseSwap {} => "RefuseSwap",
+        }
+    }
+
+    /// The schema of the kinds of move the player to move could make right now, with a legal move
+    /// of each kind as the example. Nothing is listed once the game is over.
+    pub fn describe(game_address: &Address, game: &Game, state: &GameState) -> Vec<MoveSchema> {
+        let legal_moves = state.legal_moves(game_address, game);
+        let range = game.suggestion_range.clone();
+        let number_field = |name: &str, description: &str| {
+            let mut properties = BTreeMap::new();
+            properties.insert(
+                name.to_string(),
+                FieldSchema {
+                    field_type: "integer".into(),
+                    minimum: Some(range.as_ref().map_or(0, |range| range.min)),
+                    maximum: range.as_ref().map(|range| range.max),
+                    description: description.into(),
+                },
+            );
+            properties
+        };
+        let mut kinds = Vec::new();
+        legal_moves
+            .into_iter()
+            .filter(|legal_move| {
+                let first_of_kind = !kinds.contains(&legal_move.kind());
+                kinds.push(legal_move.kind());
+                first_of_kind
+            })
+            .map(|example| {
+                let (description, properties) = match example {
+                    MoveType::Suggest { .. } => (
+                        "Pick a number for the opponent to predict",
+                        number_field("suggestion", "The number to suggest"),
+                    ),
+                    MoveType::Predict { .. } => (
+                        "Predict the number the opponent suggested",
+                        number_field("prediction", "The number you think was suggested"),
+                    ),
+                    MoveType::Swap {} => {
+                        if state.pending_swap.is_some() {
+                            ("Agree to swap suggesting and predicting", BTreeMap::new())
+                        } else {
+                            (
+                                "Swap suggesting and predicting with the opponent",
+                                BTreeMap::new(),
+                            )
+                        }
+                    }
+                    MoveType::RefuseSwap {} => (
+                        "Turn down the opponent asking to swap roles",
+                        BTreeMap::new(),
+                    ),
+                };
+                MoveSchema {
+                    kind: example.kind().into(),
+                    description: description.into(),
+                    required: properties.keys().cloned().collect(),
+                    properties,
+                    example,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{Address, Game, GameResult, Move, MoveType, SuggestionRange, SwapRules};
+
+/**
+ *
+ * A game can be recorded as a PGN style text, a block of [Tag "value"] headers followed by the numbered moves,
+ * or as JSON holding the same information. Either can be read back to play through the game again.
+ *
+ */
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameRecord {
+    pub address: Address,
+    pub game_type: String,
+    pub game: Game,
+    pub moves: Vec<Move>,
+    pub result: Option<GameResult>,
+}
+
+/// The PGN result tag, from player 1's point of view
+fn result_tag(record: &GameRecord) -> &'static str {
+    match &record.result {
+        Some(GameResult::Winner(winner)) if *winner == record.game.player_1 => "1-0",
+        Some(GameResult::Winner(_)) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+pub fn move_text(move_type: &MoveType) -> String {
+    match move_type {
+        MoveType::Suggest { suggestion } => format!("Suggest {}", suggestion),
+        MoveType::Predict { prediction } => format!("Predict {}", prediction),
+        MoveType::Swap {} => "Swap".into(),
+        MoveType::RefuseSwap {} => "RefuseSwap".into(),
+    }
+}
+
+fn parse_move_text(text: &str) -> Result<MoveType, String> {
+    let mut words = text.split_whitespace();
+    let number = |word: Option<&str>| {
+        word.and_then(|word| word.parse::<usize>().ok())
+            .ok_or_else(|| format!("Move \"{}\" needs a number", text))
+    };
+    match words.next() {
+        Some("Suggest") => Ok(MoveType::Suggest {
+            suggestion: number(words.next())?,
+        }),
+        Some("Predict") => Ok(MoveType::Predict {
+            prediction: number(words.next())?,
+        }),
+        Some("Swap") => Ok(MoveType::Swap {}),
+        Some("RefuseSwap") => Ok(MoveType::RefuseSwap {}),
+        _ => Err(format!("Unknown move \"{}\"", text)),
+    }
+}
+
+fn parse_bool(tag: &str, value: &str) -> Result<bool, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be true or false, not {}", tag, value))
+}
+
+fn parse_number<T: std::str::FromStr>(tag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be a number, not {}", tag, value))
+}
+
+impl GameRecord {
+    /// Headers first, then one numbered move per line as `<n>. <P1|P2> <move> {<timestamp>}`, then the result
+    pub fn to_pgn(&self) -> String {
+        let game = &self.game;
+        let mut headers = vec![
+            ("GameType", self.game_type.clone()),
+            ("Game", self.address.to_string()),
+            ("Player1", game.player_1.to_string()),
+            ("Player2", game.player_2.to_string()),
+            ("CreatedAt", game.created_at.to_string()),
+            ("Private", game.private.to_string()),
+        ];
+        if let Some(range) = &game.suggestion_range {
+            headers.push(("SuggestionRange", format!("{}..{}", range.min, range.max)));
+        }
+        if let Some(rules) = &game.swap_rules {
+            headers.push(("MaxSwaps", rules.max_swaps.to_string()));
+            headers.push(("SwapConsent", rules.require_consent.to_string()));
+        }
+        if let Some(rounds) = game.rounds {
+            headers.push(("Rounds", rounds.to_string()));
+        }
+        if let Some(previous) = &game.rematch_of {
+            headers.push(("RematchOf", previous.to_string()));
+        }
+        if let Some(game_match) = &game.in_match {
+            headers.push(("Match", game_match.to_string()));
+        }
+        headers.push(("Result", result_tag(self).into()));
+
+        let mut pgn: String = headers
+            .into_iter()
+            .map(|(tag, value)| format!("[{} \"{}\"]\n", tag, value))
+            .collect();
+        pgn.push('\n');
+        for (i, game_move) in self.moves.iter().enumerate() {
+            let player = if game_move.author == game.player_1 {
+                "P1"
+            } else {
+                "P2"
+            };
+            pgn.push_str(&format!(
+                "{}. {} {} {{{}}}\n",
+                i + 1,
+                player,
+                move_text(&game_move.move_type),
+                game_move.timestamp
+            ));
+        }
+        pgn.push_str(result_tag(self));
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Read a record in either format, JSON records are the ones starting with a brace
+    pub fn parse(text: &str) -> Result<GameRecord, String> {
+        if text.trim_start().starts_with('{') {
+            serde_json::from_str(text).map_err(|e| e.to_string())
+        } else {
+            GameRecord::from_pgn(text)
+        }
+    }
+
+    /// Read back a record written by to_pgn. The record does not say which entry each move followed,
+    /// so previous_move is set to the game address for every move.
+    pub fn from_pgn(pgn: &str) -> Result<GameRecord, String> {
+        let mut address = None;
+        let mut game_type = None;
+        let mut player_1 = None;
+        let mut player_2 = None;
+        let mut created_at = 0;
+        let mut private = false;
+        let mut rematch_of = None;
+        let mut in_match = None;
+        let mut suggestion_range = None;
+        let mut max_swaps = None;
+        let mut require_consent = false;
+        let mut rounds = None;
+        let mut result_tag = "*".to_string();
+        let mut moves = Vec::new();
+
+        for line in pgn.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if line.starts_with('[') && line.ends_with(']') {
+                let header = &line[1..line.len() - 1];
+                let mut parts = header.splitn(2, ' ');
+                let tag = parts.next().unwrap_or("");
+                let value = parts.next().unwrap_or("").trim().trim_matches('"');
+                match tag {
+                    "GameType" => game_type = Some(value.to_string()),
+                    "Game" => address = Some(Address::from(value.to_string())),
+                    "Player1" => player_1 = Some(Address::from(value.to_string())),
+                    "Player2" => player_2 = Some(Address::from(value.to_string())),
+                    "CreatedAt" => created_at = parse_number(tag, value)?,
+                    "Private" => private = parse_bool(tag, value)?,
+                    "SuggestionRange" => {
+                        let mut bounds = value.splitn(2, "..");
+                        suggestion_range = Some(SuggestionRange {
+                            min: parse_number(tag, bounds.next().unwrap_or(""))?,
+                            max: parse_number(tag, bounds.next().unwrap_or(""))?,
+                        });
+                    }
+                    "MaxSwaps" => max_swaps = Some(parse_number(tag, value)?),
+                    "SwapConsent" => require_consent = parse_bool(tag, value)?,
+                    "Rounds" => rounds = Some(parse_number(tag, value)?),
+                    "RematchOf" => rematch_of = Some(Address::from(value.to_string())),
+                    "Match" => in_match = Some(Address::from(value.to_string())),
+                    "Result" => result_tag = value.to_string(),
+                    // tags added by other tools are kept out of the way
+                    _ => {}
+                }
+            } else if let Some(dot) = line.find(". ") {
+                moves.push((line[..dot].to_string(), line[dot + 2..].to_string()));
+            } else if line != result_tag {
+                return Err(format!("Could not read the line \"{}\"", line));
+            }
+        }
+
+        let address = address.ok_or("The record has no Game header")?;
+        let game = Game {
+            player_1: player_1.ok_or("The record has no Player1 header")?,
+            player_2: player_2.ok_or("The record has no Player2 header")?,
+            created_at,
+            private,
+            rematch_of,
+            in_match,
+            suggestion_range,
+            swap_rules: max_swaps.map(|max_swaps| SwapRules {
+                max_swaps,
+                require_consent,
+            }),
+            rounds,
+        };
+        let moves = moves
+            .into_iter()
+            .enumerate()
+            .map(|(i, (number, text))| {
+                if number.parse::<usize>() != Ok(i + 1) {
+                    return Err(format!(
+                        "Move {} is out of order, expected move {}",
+                        number,
+                        i + 1
+                    ));
+                }
+                let (player, rest) = text.split_at(text.find(' ').unwrap_or(text.len()));
+                let author = match player {
+                    "P1" => game.player_1.clone(),
+                    "P2" => game.player_2.clone(),
+                    _ => return Err(format!("Move {} was not made by P1 or P2", number)),
+                };
+                let (move_text, timestamp) = match rest.find('{') {
+                    Some(brace) => (
+                        &rest[..brace],
+                        parse_number("The timestamp", rest[brace + 1..].trim_end_matches('}'))?,
+                    ),
+                    None => (rest, 0),
+                };
+                Ok(Move {
+                    game: address.clone(),
+                    author,
+                    move_type: parse_move_text(move_text)?,
+                    previous_move: address.clone(),
+                    timestamp,
+                })
+            })
+            .collect::<Result<Vec<Move>, String>>()?;
+        let result = match result_tag.as_str() {
+            "1-0" => Some(GameResult::Winner(game.player_1.clone())),
+            "0-1" => Some(GameResult::Winner(game.player_2.clone())),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        };
+        Ok(GameRecord {
+            address,
+            game_type: game_type.unwrap_or_else(|| crate::GAME_TYPE.into()),
+            game,
+            moves,
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> GameRecord {
+        let game = Game {
+            player_1: Address::from("alice".to_string()),
+            player_2: Address::from("bob".to_string()),
+            created_at: 5,
+            private: true,
+            rematch_of: Some(Address::from("earlier".to_string())),
+            in_match: Some(Address::from("match".to_string())),
+            suggestion_range: Some(SuggestionRange { min: 1, max: 5 }),
+            swap_rules: Some(SwapRules {
+                max_swaps: 3,
+                require_consent: true,
+            }),
+            rounds: Some(2),
+        };
+        let address = Address::from("game".to_string());
+        let moves = vec![
+            (&game.player_2, MoveType::Suggest { suggestion: 3 }),
+            (&game.player_1, MoveType::Predict { prediction: 3 }),
+            (&game.player_2, MoveType::Swap {}),
+            (&game.player_1, MoveType::RefuseSwap {}),
+            (&game.player_2, MoveType::Suggest { suggestion: 5 }),
+            (&game.player_1, MoveType::Predict { prediction: 1 }),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (author, move_type))| Move {
+            game: address.clone(),
+            author: author.clone(),
+            move_type,
+            previous_move: address.clone(),
+            timestamp: 10 + i as u32,
+        })
+        .collect();
+        GameRecord {
+            address,
+            game_type: crate::GAME_TYPE.into(),
+            game,
+            moves,
+            result: Some(GameResult::Draw),
+        }
+    }
+
+    /// Replace one line of the PGN of the test record
+    fn pgn_with(line: &str, replacement: &str) -> String {
+        let pgn = record().to_pgn();
+        assert!(pgn.contains(line), "{} is not in\n{}", line, pgn);
+        pgn.replace(line, replacement)
+    }
+
+    #[test]
+    fn pgn_round_trip() {
+        let original = record();
+        let pgn = original.to_pgn();
+        assert!(pgn.contains("[SuggestionRange \"1..5\"]\n"));
+        assert!(pgn.contains("3. P2 Swap {12}\n"));
+        let read = GameRecord::parse(&pgn).unwrap();
+        assert_eq!(read.to_pgn(), pgn);
+        assert_eq!(read.moves, original.moves);
+        assert_eq!(read.result, original.result);
+        assert_eq!(read.game.suggestion_range, original.game.suggestion_range);
+        assert_eq!(read.game.swap_rules, original.game.swap_rules);
+        assert_eq!(read.game.rounds, original.game.rounds);
+        assert_eq!(read.game.rematch_of, original.game.rematch_of);
+        assert_eq!(read.game.in_match, original.game.in_match);
+    }
+
+    #[test]
+    fn json_records_are_detected() {
+        let original = record();
+        let json = serde_json::to_string_pretty(&original).unwrap();
+        let read = GameRecord::parse(&format!("\n  {}", json)).unwrap();
+        assert_eq!(read.to_pgn(), original.to_pgn());
+        assert!(GameRecord::parse("{ not json").is_err());
+    }
+
+    #[test]
+    fn bad_headers_are_rejected() {
+        for (line, replacement) in &[
+            ("[CreatedAt \"5\"]", "[CreatedAt \"yesterday\"]"),
+            ("[Private \"true\"]", "[Private \"maybe\"]"),
+            ("[SuggestionRange \"1..5\"]", "[SuggestionRange \"1-5\"]"),
+            ("[MaxSwaps \"3\"]", "[MaxSwaps \"-1\"]"),
+            ("[Game \"game\"]\n", ""),
+            ("[Player2 \"bob\"]\n", ""),
+        ] {
+            assert!(
+                GameRecord::from_pgn(&pgn_with(line, replacement)).is_err(),
+                "{} was accepted",
+                replacement
+            );
+        }
+    }
+
+    #[test]
+    fn bad_moves_are_rejected() {
+        for (line, replacement) in &[
+            ("1. P2 Suggest 3 {10}", "1. P3 Suggest 3 {10}"),
+            ("1. P2 Suggest 3 {10}", "1. P2 Jump 3 {10}"),
+            ("1. P2 Suggest 3 {10}", "1. P2 Suggest three {10}"),
+            ("2. P1 Predict 3 {11}", "2. P1 Predict {11}"),
+            ("1. P2 Suggest 3 {10}", "P2 Suggest 3 {10}"),
+        ] {
+            assert!(
+                GameRecord::from_pgn(&pgn_with(line, replacement)).is_err(),
+                "{} was accepted",
+                replacement
+            );
+        }
+    }
+
+    #[test]
+    fn bad_timestamps_are_rejected() {
+        for replacement in &[
+            "1. P2 Suggest 3 {ten}",
+            "1. P2 Suggest 3 {-10}",
+            "1. P2 Suggest 3 {}",
+        ] {
+            assert!(
+                GameRecord::from_pgn(&pgn_with("1. P2 Suggest 3 {10}", replacement)).is_err(),
+                "{} was accepted",
+                replacement
+            );
+        }
+    }
+
+    #[test]
+    fn move_numbers_must_follow_on() {
+        let gap = pgn_with("3. P2 Swap {12}", "4. P2 Swap {12}");
+        assert!(GameRecord::from_pgn(&gap).is_err());
+        let reordered = pgn_with(
+            "1. P2 Suggest 3 {10}\n2. P1 Predict 3 {11}",
+            "2. P1 Predict 3 {11}\n1. P2 Suggest 3 {10}",
+        );
+        assert!(GameRecord::from_pgn(&reordered).is_err());
+    }
+}
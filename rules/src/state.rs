@@ -0,0 +1,307 @@
+//! As a game autor you get to decide what the State object of your game looks like.
+//! Most of the time you want it to include all of the previous moves as well.
+//!
+//! To customize the game state implement your own GameState struct. This must have a function called 'initial()'
+//! which return the initial state.
+
+#[cfg(feature = "holochain")]
+use hdk::holochain_json_api::{error::JsonError, json::JsonString};
+
+use crate::{Address, Game, Move, MoveType};
+
+/// How many numbers are predicted (successfully or not) in a new game before it is over
+pub const ROUNDS_PER_GAME: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "holochain", derive(DefaultJson))]
+pub enum GameResult {
+    Winner(Address),
+    Draw,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "holochain", derive(DefaultJson))]
+pub struct GameState {
+    // <<DEVCAMP-TODO>>
+    pub moves: Vec<Move>,
+    pub suggestion: usize,
+    pub player_1_successful_suggestion: usize,
+    pub player_1_successful_prediction: usize,
+    pub player_1_suggestion_attempts: usize,
+    pub player_1_prediction_attempts: usize,
+    pub player_2_successful_suggestion: usize,
+    pub player_2_successful_prediction: usize,
+    pub player_2_suggestion_attempts: usize,
+    pub player_2_prediction_attempts: usize,
+    pub player_2_suggests: bool,
+    pub swaps_made: usize,
+    /// The player waiting for their opponent to agree to a swap, in games where swaps need consent
+    pub pending_swap: Option<Address>,
+    // Implement your own game state
+    // May be helpful to split this into state for each player
+}
+
+impl GameState {
+    pub fn initial() -> Self {
+        // <DEVCAMP> return an initial state of a game
+        Self {
+            moves: Vec::new(),
+            suggestion: 0,
+            player_1_successful_suggestion: 0,
+            player_1_successful_prediction: 0,
+            player_1_suggestion_attempts: 0,
+            player_1_prediction_attempts: 0,
+            player_2_successful_suggestion: 0,
+            player_2_successful_prediction: 0,
+            player_2_suggestion_attempts: 0,
+            player_2_prediction_attempts: 0,
+            player_2_suggests: true,
+            swaps_made: 0,
+            pending_swap: None,
+        }
+    }
+
+    /// Every prediction ends a round, whoever made it
+    pub fn rounds_played(&self) -> usize {
+        self.moves
+            .iter()
+            .filter(|game_move| match game_move.move_type {
+                MoveType::Predict { .. } => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    /// A player scores a point for each correct prediction and each of their suggestions the opponent failed to predict
+    pub fn player_1_score(&self) -> usize {
+        self.player_1_successful_prediction + self.player_1_successful_suggestion
+    }
+
+    pub fn player_2_score(&self) -> usize {
+        self.player_2_successful_prediction + self.player_2_successful_suggestion
+    }
+
+    /// The result of the game, or None if it is still being played
+    pub fn result(&self, game: &Game) -> Option<GameResult> {
+        let rounds = game.rounds?;
+        if self.rounds_played() < rounds {
+            return None;
+        }
+        if self.player_1_score() > self.player_2_score() {
+            Some(GameResult::Winner(game.player_1.clone()))
+        } else if self.player_2_score() > self.player_1_score() {
+            Some(GameResult::Winner(game.player_2.clone()))
+        } else {
+            Some(GameResult::Draw)
+        }
+    }
+
+    pub fn suggester(&self, game: &Game) -> Address {
+        if self.player_2_suggests {
+            game.player_2.clone()
+        } else {
+            game.player_1.clone()
+        }
+    }
+
+    /// A round is in progress once a number has been suggested and until it is predicted
+    pub fn between_rounds(&self) -> bool {
+        match self.moves.last() {
+            Some(Move {
+                move_type: MoveType::Suggest { .. },
+                ..
+            }) => false,
+            _ => true,
+        }
+    }
+
+    /// The player who is expected to make the next move, or None if nobody can move.
+    /// Between rounds the suggester starts the next round (player 2 for the first one), during a round the other player
+    /// has to predict and a swap proposal has to be answered by the opponent before anything else.
+    pub fn next_to_move(&self, game: &Game) -> Option<Address> {
+        if self.result(game).is_some() {
+            return None;
+        }
+        if let Some(proposer) = &self.pending_swap {
+            return Some(game.opponent_of(proposer));
+        }
+        let suggester = self.suggester(game);
+        if self.between_rounds() {
+            Some(suggester)
+        } else {
+            Some(game.opponent_of(&suggester))
+        }
+    }
+
+    /// Every move the player to move could make right now. Games created before the suggestion range was stored accept
+    /// any number, only the numbers of the default range are listed for them.
+    pub fn legal_moves(&self, game_address: &Address, game: &Game) -> Vec<MoveType> {
+        let player = match self.next_to_move(game) {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+        let range = game.suggestion_range.clone().unwrap_or_default();
+        (range.min..=range.max)
+            .flat_map(|number| {
+                vec![
+                    MoveType::Suggest { suggestion: number },
+                    MoveType::Predict { prediction: number },
+                ]
+            })
+            .chain(vec![MoveType::Swap {}, MoveType::RefuseSwap {}])
+            .filter(|move_type| {
+                Move {
+                    game: game_address.clone(),
+                    author: player.clone(),
+                    move_type: move_type.clone(),
+                    previous_move: game_address.clone(),
+                    timestamp: 0,
+                }
+                .is_valid(game.clone(), self.clone())
+                .is_ok()
+            })
+            .collect()
+    }
+
+    pub fn render(&self, game: &Game) -> String {
+        // <<DEVCAMP>> return a pretty formatting string representation
+        let game_string;
+        let last_move = self.moves.last();
+        // determine what kinf of string to display based on whose turn it
+        // is currently and what kinf of move is allowed for that player.
+        let player_number = |player: Option<Address>| {
+            if player == Some(game.player_1.clone()) {
+                1
+            } else {
+                2
+            }
+        };
+        let waiting_string;
+        match (self.result(game), last_move) {
+            (Some(GameResult::Winner(winner)), _) => {
+                if winner == game.player_1 {
+                    game_string = "Game over, player 1 wins!"
+                } else {
+                    game_string = "Game over, player 2 wins!"
+                }
+            }
+            (Some(GameResult::Draw), _) => game_string = "Game over, it's a draw!",
+            (None, Some(_)) => {
+                let next = player_number(self.next_to_move(game));
+                waiting_string = if self.pending_swap.is_some() {
+                    format!(
+                        "Waiting for player {} to accept or refuse swapping roles...",
+                        next
+                    )
+                } else if self.between_rounds() {
+                    format!("Waiting for player {} to suggest a number...", next)
+                } else {
+                    format!(
+                        "Waiting for player {} to predict the suggested number...",
+                        next
+                    )
+                };
+                game_string = &waiting_string
+            }
+            // when no one else made a move yet, player 2 must always make the first move so
+            (None, None) => game_string = "Waiting for player 2 to suggest a number",
+        }
+        let range_string = match &game.suggestion_range {
+            Some(range) => format!("numbers from {} to {}", range.min, range.max),
+            None => "any number".to_string(),
+        };
+        let swaps_string = match &game.swap_rules {
+            Some(rules) => format!("{}/{}", self.swaps_made, rules.max_swaps),
+            None => format!("{}", self.swaps_made),
+        };
+        format!(" {} ({}, swaps used {}) \nplayer 1 record: \n\tsuggestion: {}/{} \n\tprediction: {}/{} \nplayer 2 record: \n\tsuggestion: {}/{} \n\tprediction: {}/{}\n", game_string, range_string, swaps_string, self.player_1_successful_suggestion, self.player_1_suggestion_attempts, self.player_1_successful_prediction, self.player_1_prediction_attempts, self.player_2_successful_suggestion, self.player_2_suggestion_attempts, self.player_2_successful_prediction, self.player_2_prediction_attempts)
+    }
+
+    pub fn evolve(&self, game: Game, next_move: &Move) -> GameState {
+        // <<DEVCAMP>>
+        // given a current state, a game and a move, compute the next state
+        // You can assume all moves are valid
+
+        //unpack the move
+        let mut moves = self.moves.clone();
+        let mut new_suggestion = self.suggestion;
+        let mut player_1_successful_suggestion = self.player_1_successful_suggestion;
+        let mut player_1_successful_prediction = self.player_1_successful_prediction;
+        let mut player_1_suggestion_attempts = self.player_1_suggestion_attempts;
+        let mut player_1_prediction_attempts = self.player_1_prediction_attempts;
+
+        let mut player_2_successful_suggestion = self.player_2_successful_suggestion;
+        let mut player_2_successful_prediction = self.player_2_successful_prediction;
+        let mut player_2_suggestion_attempts = self.player_2_suggestion_attempts;
+        let mut player_2_prediction_attempts = self.player_2_prediction_attempts;
+
+        let mut player_2_suggests = self.player_2_suggests;
+        let mut swaps_made = self.swaps_made;
+        let mut pending_swap = self.pending_swap.clone();
+
+        //add the new move to the state
+        moves.push(next_move.clone());
+
+        match next_move.move_type {
+            // match to all the available MoveTypes.
+            MoveType::Suggest { suggestion } => {
+                new_suggestion = suggestion;
+                // figure out which player made the move
+                if game.player_1 == next_move.author {
+                    player_1_suggestion_attempts += 1
+                } else {
+                    player_2_suggestion_attempts += 1
+                }
+            }
+            MoveType::Predict { prediction } => {
+                if game.player_1 == next_move.author {
+                    if new_suggestion == prediction {
+                        player_1_successful_prediction += 1
+                    } else {
+                        player_2_successful_suggestion += 1
+                    }
+                    player_1_prediction_attempts += 1
+                } else if game.player_2 == next_move.author {
+                    if new_suggestion == prediction {
+                        player_2_successful_prediction += 1
+                    } else {
+                        player_1_successful_suggestion += 1
+                    }
+                    player_2_prediction_attempts += 1
+                }
+            }
+            MoveType::Swap {} => {
+                let require_consent = game
+                    .swap_rules
+                    .as_ref()
+                    .map_or(false, |rules| rules.require_consent);
+                if require_consent && pending_swap.is_none() {
+                    // only a proposal until the opponent swaps too
+                    pending_swap = Some(next_move.author.clone())
+                } else {
+                    pending_swap = None;
+                    swaps_made += 1;
+                    player_2_suggests = !player_2_suggests;
+                }
+            }
+            MoveType::RefuseSwap {} => pending_swap = None,
+        }
+
+        //finally return the new state
+        GameState {
+            moves,
+            suggestion: new_suggestion,
+            player_1_successful_suggestion,
+            player_1_successful_prediction,
+            player_1_suggestion_attempts,
+            player_1_prediction_attempts,
+            player_2_successful_suggestion,
+            player_2_successful_prediction,
+            player_2_suggestion_attempts,
+            player_2_prediction_attempts,
+            player_2_suggests,
+            swaps_made,
+            pending_swap,
+        }
+    }
+}
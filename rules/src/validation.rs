@@ -0,0 +1,138 @@
+//! To implement your own custom rule validation all you need to do is re-implement the function 'is_valid' on 'Move'
+//!
+//! This function takes the current game and the game state (which includes all the existing moves)
+//! and deermines if a new candidate move is valid. Typically this will involve first matching on the move type
+//! and then determining if the move is valid.
+//!
+//! It function must return Ok(()) if a move is valid and Err("Some error string") for an invalid move.
+//! It is useful to provide descriptive error strings as these can be visible to the end user.
+
+use crate::{Address, Game, GameState, Move, MoveType};
+
+impl Move {
+    pub fn is_valid(&self, game: Game, game_state: GameState) -> Result<(), String> {
+        // <<DEVCAMP-TODO>> Check if a move is valid given the current game and its state
+        let is_player_turn_result = is_players_turn(self.author.clone(), &game, &game_state)?;
+        is_the_right_move(&game_state, &self.move_type, is_player_turn_result, &game)?;
+        Ok(())
+    }
+}
+
+// some helper function for the validation of the moves
+
+fn is_players_turn(
+    player: Address,
+    game: &Game,
+    game_state: &GameState,
+) -> Result<Address, String> {
+    match game_state.next_to_move(game) {
+        Some(next_player) => {
+            if next_player == player {
+                Ok(player)
+            } else if game_state.moves.is_empty() {
+                Err("Player 2 must start the game".into()) // player 2 can start first by convention
+            } else {
+                Err("It is not this player turn".into())
+            }
+        }
+        None => Err("No more moves can be made in this game".into()),
+    }
+}
+
+fn is_the_right_move(
+    game_state: &GameState,
+    move_type: &MoveType,
+    player: Address,
+    game: &Game,
+) -> Result<(), String> {
+    // games from before the range was stored on the game accept any number
+    if let Some(range) = &game.suggestion_range {
+        match move_type {
+            MoveType::Suggest { suggestion: number } | MoveType::Predict { prediction: number }
+                if !range.contains(*number) =>
+            {
+                return Err(format!(
+                    "Numbers in this game must be between {} and {}",
+                    range.min, range.max
+                ));
+            }
+            _ => {}
+        }
+    }
+    is_allowed_swap(game_state, move_type, game)?;
+    let player_2_suggest = game_state.player_2_suggests;
+    // determine if the current player should suggest or predict
+    if player == game.player_2 {
+        if player_2_suggest {
+            match move_type {
+                MoveType::Suggest { .. } => Ok(()),
+                MoveType::Predict { .. } => {
+                    Err("Player 2 must suggest, not predict. Use swap to switch roles".into())
+                }
+                _ => Ok(()),
+            }
+        } else {
+            match move_type {
+                MoveType::Suggest { .. } => {
+                    Err("Player 2 must predict, not suggest. Use Swap switch roles".into())
+                }
+                MoveType::Predict { .. } => Ok(()),
+                _ => Ok(()),
+            }
+        }
+    } else {
+        if player_2_suggest {
+            match move_type {
+                MoveType::Suggest { .. } => {
+                    Err("Player 1 must predict not suggest. Use swap switch roles".into())
+                }
+                MoveType::Predict { .. } => Ok(()),
+                _ => Ok(()),
+            }
+        } else {
+            match move_type {
+                MoveType::Suggest { .. } => Ok(()),
+                MoveType::Predict { .. } => {
+                    Err("Player 1 must suggest not predict. Use swap to switch roles".into())
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// Swaps and answers to swap proposals. Games created before there were swap rules have no limit and need no consent,
+/// but every game only allows swapping between rounds.
+fn is_allowed_swap(
+    game_state: &GameState,
+    move_type: &MoveType,
+    game: &Game,
+) -> Result<(), String> {
+    if game_state.pending_swap.is_some() {
+        return match move_type {
+            MoveType::Swap {} | MoveType::RefuseSwap {} => Ok(()),
+            _ => Err(
+                "The opponent asked to swap roles. Accept with Swap or refuse with RefuseSwap"
+                    .into(),
+            ),
+        };
+    }
+    match move_type {
+        MoveType::Swap {} => {
+            if !game_state.between_rounds() {
+                return Err("Roles can only be swapped between rounds".into());
+            }
+            if let Some(rules) = &game.swap_rules {
+                if game_state.swaps_made >= rules.max_swaps {
+                    return Err(format!(
+                        "Only {} swaps are allowed in this game",
+                        rules.max_swaps
+                    ));
+                }
+            }
+            Ok(())
+        }
+        MoveType::RefuseSwap {} => Err("There is no swap to refuse".into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,504 @@
+/**
+ * Self-play simulator for the rules. Games with random rules are played with random move sequences, mostly moves the
+ * rules allow with illegal ones mixed in, and the invariants below are checked after every move. A failing sequence
+ * is shrunk to the shortest, simplest one that still fails before it is reported, together with the seed to replay it.
+ */
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use rules::{Address, Game, GameState, Move, MoveType, SuggestionRange, SwapRules};
+
+const GAMES: u64 = 500;
+const MAX_MOVES: usize = 80;
+
+/// Which player makes the move, and the move
+type Action = (bool, MoveType);
+
+fn player_1() -> Address {
+    Address::from("player_1".to_string())
+}
+
+fn player_2() -> Address {
+    Address::from("player_2".to_string())
+}
+
+fn game_address() -> Address {
+    Address::from("game".to_string())
+}
+
+fn random_game(rng: &mut StdRng) -> Game {
+    let min = rng.gen_range(0, 3);
+    Game {
+        player_1: player_1(),
+        player_2: player_2(),
+        created_at: 0,
+        private: false,
+        rematch_of: None,
+        in_match: None,
+        suggestion_range: if rng.gen_bool(0.8) {
+            Some(SuggestionRange {
+                min,
+                max: min + rng.gen_range(0, 6),
+            })
+        } else {
+            None
+        },
+        swap_rules: if rng.gen_bool(0.8) {
+            Some(SwapRules {
+                max_swaps: rng.gen_range(0, 4),
+                require_consent: rng.gen_bool(0.5),
+            })
+        } else {
+            None
+        },
+        rounds: if rng.gen_bool(0.9) {
+            Some(rng.gen_range(1, 12))
+        } else {
+            None
+        },
+    }
+}
+
+/// Any move by either player, including numbers outside the range of the game
+fn random_move(rng: &mut StdRng) -> Action {
+    let number = rng.gen_range(0, 12);
+    let move_type = match rng.gen_range(0, 4) {
+        0 => MoveType::Suggest { suggestion: number },
+        1 => MoveType::Predict { prediction: number },
+        2 => MoveType::Swap {},
+        _ => MoveType::RefuseSwap {},
+    };
+    (rng.gen_bool(0.5), move_type)
+}
+
+/// Mostly legal moves, played until the game is over, with an illegal move now and then
+fn random_actions(rng: &mut StdRng, game: &Game) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut state = GameState::initial();
+    while actions.len() < MAX_MOVES {
+        let legal_move = state.next_to_move(game).and_then(|player| {
+            let legal_moves = state.legal_moves(&game_address(), game);
+            legal_moves
+                .choose(rng)
+                .map(|move_type| (player == game.player_1, move_type.clone()))
+        });
+        let action = match legal_move {
+            Some(action) if rng.gen_bool(0.85) => action,
+            _ => random_move(rng),
+        };
+        let game_move = to_move(&action);
+        if game_move.is_valid(game.clone(), state.clone()).is_ok() {
+            state = state.evolve(game.clone(), &game_move);
+        }
+        actions.push(action);
+        if state.result(game).is_some() && rng.gen_bool(0.5) {
+            break;
+        }
+    }
+    actions
+}
+
+fn to_move((by_player_1, move_type): &Action) -> Move {
+    Move {
+        game: game_address(),
+        author: if *by_player_1 { player_1() } else { player_2() },
+        move_type: move_type.clone(),
+        previous_move: game_address(),
+        timestamp: 0,
+    }
+}
+
+fn check_state(game: &Game, state: &GameState) -> Result<(), String> {
+    let suggestions = |author: Address| {
+        state
+            .moves
+            .iter()
+            .filter(|game_move| match game_move.move_type {
+                MoveType::Suggest { .. } => game_move.author == author,
+                _ => false,
+            })
+            .count()
+    };
+    let predictions = |author: Address| {
+        state
+            .moves
+            .iter()
+            .filter(|game_move| match game_move.move_type {
+                MoveType::Predict { .. } => game_move.author == author,
+                _ => false,
+            })
+            .count()
+    };
+    let counts = [
+        (
+            "player 1 suggestion",
+            state.player_1_suggestion_attempts,
+            suggestions(player_1()),
+        ),
+        (
+            "player 2 suggestion",
+            state.player_2_suggestion_attempts,
+            suggestions(player_2()),
+        ),
+        (
+            "player 1 prediction",
+            state.player_1_prediction_attempts,
+            predictions(player_1()),
+        ),
+        (
+            "player 2 prediction",
+            state.player_2_prediction_attempts,
+            predictions(player_2()),
+        ),
+    ];
+    for (name, attempts, made) in counts.iter() {
+        if attempts != made {
+            return Err(format!(
+                "{} attempts is {} but {} were made",
+                name, attempts, made
+            ));
+        }
+    }
+    let successes = [
+        (
+            "player 1 suggestion",
+            state.player_1_successful_suggestion,
+            state.player_1_suggestion_attempts,
+        ),
+        (
+            "player 2 suggestion",
+            state.player_2_successful_suggestion,
+            state.player_2_suggestion_attempts,
+        ),
+        (
+            "player 1 prediction",
+            state.player_1_successful_prediction,
+            state.player_1_prediction_attempts,
+        ),
+        (
+            "player 2 prediction",
+            state.player_2_successful_prediction,
+            state.player_2_prediction_attempts,
+        ),
+    ];
+    for (name, successful, attempts) in successes.iter() {
+        if successful > attempts {
+            return Err(format!(
+                "{} has {} successes from {} attempts",
+                name, successful, attempts
+            ));
+        }
+    }
+    // every prediction scores a point for one of the players
+    if state.player_1_score() + state.player_2_score() != state.rounds_played() {
+        return Err(format!(
+            "the scores add up to {} after {} rounds",
+            state.player_1_score() + state.player_2_score(),
+            state.rounds_played()
+        ));
+    }
+    if let Some(rules) = &game.swap_rules {
+        if state.swaps_made > rules.max_swaps {
+            return Err(format!(
+                "{} swaps were made out of {}",
+                state.swaps_made, rules.max_swaps
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// What evolve should make of the moves, worked out from the actions alone without the validation of the rules crate.
+/// Player 1 is index 0 and player 2 index 1.
+#[derive(Debug, Default)]
+struct Model {
+    suggestion: usize,
+    suggestion_attempts: [usize; 2],
+    prediction_attempts: [usize; 2],
+    successful_suggestions: [usize; 2],
+    successful_predictions: [usize; 2],
+    player_1_suggests: bool,
+    /// A suggestion is waiting to be predicted
+    in_round: bool,
+    swaps_made: usize,
+    /// The player who asked to swap, in games where swaps need consent
+    pending_swap: Option<usize>,
+}
+
+impl Model {
+    /// Apply a move the rules accepted, failing if it does not fit the roles the model expects
+    fn apply(&mut self, game: &Game, (by_player_1, move_type): &Action) -> Result<(), String> {
+        let player = if *by_player_1 { 0 } else { 1 };
+        let suggester = if self.player_1_suggests { 0 } else { 1 };
+        match (move_type, self.pending_swap) {
+            (MoveType::Swap {}, Some(proposer)) | (MoveType::RefuseSwap {}, Some(proposer))
+                if proposer == player =>
+            {
+                Err("a swap was answered by the player who asked for it".into())
+            }
+            (MoveType::Swap {}, Some(_)) => {
+                self.pending_swap = None;
+                self.swap_roles();
+                Ok(())
+            }
+            (MoveType::RefuseSwap {}, Some(_)) => {
+                self.pending_swap = None;
+                Ok(())
+            }
+            (_, Some(_)) => Err("a move was made while a swap was waiting for an answer".into()),
+            (MoveType::RefuseSwap {}, None) => {
+                Err("a swap was refused without being asked for".into())
+            }
+            (_, None) if player == suggester && self.in_round => {
+                Err("the suggester moved during a round".into())
+            }
+            (_, None) if player != suggester && !self.in_round => {
+                Err("the predicter moved between rounds".into())
+            }
+            (MoveType::Swap {}, None) => {
+                if game
+                    .swap_rules
+                    .as_ref()
+                    .is_some_and(|rules| rules.require_consent)
+                {
+                    self.pending_swap = Some(player);
+                } else {
+                    self.swap_roles();
+                }
+                Ok(())
+            }
+            (MoveType::Suggest { suggestion }, None) => {
+                self.suggestion = *suggestion;
+                self.suggestion_attempts[player] += 1;
+                self.in_round = true;
+                Ok(())
+            }
+            (MoveType::Predict { prediction }, None) => {
+                self.prediction_attempts[player] += 1;
+                if *prediction == self.suggestion {
+                    self.successful_predictions[player] += 1;
+                } else {
+                    self.successful_suggestions[suggester] += 1;
+                }
+                self.in_round = false;
+                Ok(())
+            }
+        }
+    }
+
+    fn swap_roles(&mut self) {
+        self.player_1_suggests = !self.player_1_suggests;
+        self.swaps_made += 1;
+    }
+
+    /// Compare the state evolve produced with the model
+    fn check(&self, state: &GameState) -> Result<(), String> {
+        let fields = [
+            ("suggestion", state.suggestion, self.suggestion),
+            (
+                "player 1 suggestion attempts",
+                state.player_1_suggestion_attempts,
+                self.suggestion_attempts[0],
+            ),
+            (
+                "player 2 suggestion attempts",
+                state.player_2_suggestion_attempts,
+                self.suggestion_attempts[1],
+            ),
+            (
+                "player 1 prediction attempts",
+                state.player_1_prediction_attempts,
+                self.prediction_attempts[0],
+            ),
+            (
+                "player 2 prediction attempts",
+                state.player_2_prediction_attempts,
+                self.prediction_attempts[1],
+            ),
+            (
+                "player 1 successful suggestions",
+                state.player_1_successful_suggestion,
+                self.successful_suggestions[0],
+            ),
+            (
+                "player 2 successful suggestions",
+                state.player_2_successful_suggestion,
+                self.successful_suggestions[1],
+            ),
+            (
+                "player 1 successful predictions",
+                state.player_1_successful_prediction,
+                self.successful_predictions[0],
+            ),
+            (
+                "player 2 successful predictions",
+                state.player_2_successful_prediction,
+                self.successful_predictions[1],
+            ),
+            ("swaps made", state.swaps_made, self.swaps_made),
+        ];
+        for (name, actual, expected) in fields.iter() {
+            if actual != expected {
+                return Err(format!("{} is {} but should be {}", name, actual, expected));
+            }
+        }
+        if state.player_2_suggests == self.player_1_suggests {
+            return Err(format!(
+                "player_2_suggests is {} but should be {}",
+                state.player_2_suggests, !self.player_1_suggests
+            ));
+        }
+        let pending_swap = self.pending_swap.map(|proposer| {
+            if proposer == 0 {
+                player_1()
+            } else {
+                player_2()
+            }
+        });
+        if state.pending_swap != pending_swap {
+            return Err(format!(
+                "pending_swap is {:?} but should be {:?}",
+                state.pending_swap, pending_swap
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether legal_moves lists the move. Games without a suggestion range accept any number but only
+/// the numbers of the default range are listed, so for them any number counts if the kind of move is listed.
+fn listed(game: &Game, state: &GameState, move_type: &MoveType) -> bool {
+    let legal_moves = state.legal_moves(&game_address(), game);
+    match move_type {
+        MoveType::Suggest { .. } | MoveType::Predict { .. } if game.suggestion_range.is_none() => {
+            legal_moves
+                .iter()
+                .any(|legal_move| legal_move.kind() == move_type.kind())
+        }
+        _ => legal_moves.contains(move_type),
+    }
+}
+
+/// Play the actions, skipping the ones the rules reject, and check the invariants after each of them
+fn run(game: &Game, actions: &[Action]) -> Result<(), String> {
+    let mut state = GameState::initial();
+    let mut model = Model::default();
+    for (i, action) in actions.iter().enumerate() {
+        let game_move = to_move(action);
+        let valid = game_move.is_valid(game.clone(), state.clone()).is_ok();
+        let next_to_move = state.next_to_move(game);
+        // legal_moves is validation applied to every move, so this catches it disagreeing with next_to_move
+        let legal = next_to_move == Some(game_move.author.clone())
+            && listed(game, &state, &game_move.move_type);
+        if valid != legal {
+            return Err(format!(
+                "move {} is {} but legal_moves {} it",
+                i + 1,
+                if valid { "valid" } else { "invalid" },
+                if legal { "lists" } else { "does not list" }
+            ));
+        }
+        if !valid {
+            continue;
+        }
+        // players take turns, except that whoever accepts a swap goes on to suggest
+        if let Some(last_move) = state.moves.last() {
+            let require_consent = game
+                .swap_rules
+                .as_ref()
+                .is_some_and(|rules| rules.require_consent);
+            let accepted_swap = require_consent
+                && last_move.move_type == (MoveType::Swap {})
+                && state.pending_swap.is_none();
+            if last_move.author == game_move.author && !accepted_swap {
+                return Err(format!(
+                    "move {} is the second move in a row by the same player",
+                    i + 1
+                ));
+            }
+        }
+        let before = state.moves.len();
+        state = state.evolve(game.clone(), &game_move);
+        if state.moves.len() != before + 1 || state.moves.last() != Some(&game_move) {
+            return Err(format!("move {} was not added to the state", i + 1));
+        }
+        check_state(game, &state).map_err(|e| format!("after move {}: {}", i + 1, e))?;
+        // whatever validation accepted has to be a move the model allows, and evolve has to agree with the model
+        model
+            .apply(game, action)
+            .and_then(|_| model.check(&state))
+            .map_err(|e| format!("after move {}: {}", i + 1, e))?;
+        if state.result(game).is_some() && state.next_to_move(game).is_some() {
+            return Err(format!(
+                "after move {}: the game is over but someone can still move",
+                i + 1
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Smaller versions of a failing sequence: without one of its moves, or with one of its numbers made smaller
+fn simpler(actions: &[Action]) -> Vec<Vec<Action>> {
+    let mut candidates = Vec::new();
+    for i in 0..actions.len() {
+        let mut without = actions.to_vec();
+        without.remove(i);
+        candidates.push(without);
+    }
+    for (i, (by_player_1, move_type)) in actions.iter().enumerate() {
+        let smaller = match move_type {
+            MoveType::Suggest { suggestion } if *suggestion > 0 => MoveType::Suggest {
+                suggestion: suggestion - 1,
+            },
+            MoveType::Predict { prediction } if *prediction > 0 => MoveType::Predict {
+                prediction: prediction - 1,
+            },
+            _ => continue,
+        };
+        let mut simplified = actions.to_vec();
+        simplified[i] = (*by_player_1, smaller);
+        candidates.push(simplified);
+    }
+    candidates
+}
+
+fn shrink(game: &Game, actions: Vec<Action>) -> (Vec<Action>, String) {
+    let mut smallest = actions;
+    let mut error = run(game, &smallest).unwrap_err();
+    while let Some((candidate, candidate_error)) = simpler(&smallest)
+        .into_iter()
+        .filter_map(|candidate| run(game, &candidate).err().map(|e| (candidate, e)))
+        .next()
+    {
+        smallest = candidate;
+        error = candidate_error;
+    }
+    (smallest, error)
+}
+
+#[test]
+fn random_games_keep_the_invariants() {
+    for seed in 0..GAMES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let game = random_game(&mut rng);
+        let actions = random_actions(&mut rng, &game);
+        if run(&game, &actions).is_err() {
+            let (actions, error) = shrink(&game, actions);
+            let moves: Vec<String> = actions
+                .iter()
+                .map(|(by_player_1, move_type)| {
+                    format!("{} {:?}", if *by_player_1 { "P1" } else { "P2" }, move_type)
+                })
+                .collect();
+            panic!(
+                "seed {}: {}\ngame: {:?}\nmoves:\n{}",
+                seed,
+                error,
+                game,
+                moves.join("\n")
+            );
+        }
+    }
+}
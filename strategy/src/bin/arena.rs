@@ -0,0 +1,73 @@
+use structopt::StructOpt;
+
+use rules::{Game, SuggestionRange, SwapRules, ROUNDS_PER_GAME};
+use strategy::harness;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "arena",
+    about = "Play two strategies against each other without a conductor."
+)]
+struct Arena {
+    /// The first strategy (random, greedy, minimax or frequency)
+    first: String,
+    /// The strategy it plays against
+    second: String,
+    /// How many games to play, the strategies take turns at starting
+    #[structopt(long = "games", default_value = "100")]
+    games: usize,
+    /// The smallest number that can be suggested
+    #[structopt(long = "min", default_value = "1")]
+    min: usize,
+    /// The largest number that can be suggested
+    #[structopt(long = "max", default_value = "10")]
+    max: usize,
+    /// How many times the players can swap roles in each game
+    #[structopt(long = "swaps", default_value = "2")]
+    swaps: usize,
+    /// Swapping needs both players to agree
+    #[structopt(long = "consent")]
+    consent: bool,
+}
+
+fn main() {
+    let arena = Arena::from_args();
+    let rules = Game {
+        player_1: String::new(),
+        player_2: String::new(),
+        created_at: 0,
+        private: false,
+        rematch_of: None,
+        in_match: None,
+        suggestion_range: Some(SuggestionRange {
+            min: arena.min,
+            max: arena.max,
+        }),
+        swap_rules: Some(SwapRules {
+            max_swaps: arena.swaps,
+            require_consent: arena.consent,
+        }),
+        rounds: Some(ROUNDS_PER_GAME),
+    };
+    let result = strategy::named(&arena.first).and_then(|mut first| {
+        let mut second = strategy::named(&arena.second)?;
+        harness::play_match(&rules, &mut *first, &mut *second, arena.games)
+    });
+    match result {
+        Ok(report) => println!(
+            "{} vs {} over {} games: {} won {}, {} won {}, {} drawn",
+            arena.first,
+            arena.second,
+            arena.games,
+            arena.first,
+            report.first_wins,
+            arena.second,
+            report.second_wins,
+            report.draws
+        ),
+        Err(e) => {
+            println!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+use rules::{Address, Game, GameState, MoveType};
+
+use crate::Strategy;
+
+/// Predicts the number the opponent has suggested most often, in this game and every other game against them the
+/// strategy has seen. Its own suggestions are random so they can't be learned the same way, and it never asks to swap.
+#[derive(Default)]
+pub struct FrequencyStrategy {
+    /// For each game (told apart by its players and when it was created), who suggested which numbers in it so far
+    suggestions: HashMap<(Address, Address, u32), Vec<(Address, usize)>>,
+}
+
+impl FrequencyStrategy {
+    fn most_suggested_by(&self, opponent: &Address, candidates: &[usize]) -> Option<usize> {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        self.suggestions
+            .values()
+            .flatten()
+            .filter(|(author, _)| author == opponent)
+            .for_each(|(_, number)| *counts.entry(*number).or_insert(0) += 1);
+        candidates
+            .iter()
+            .filter(|number| counts.contains_key(number))
+            .max_by_key(|number| (counts[*number], std::cmp::Reverse(**number)))
+            .cloned()
+    }
+}
+
+impl Strategy for FrequencyStrategy {
+    fn observe(&mut self, game: &Game, state: &GameState) {
+        let numbers = state
+            .moves
+            .iter()
+            .filter_map(|game_move| match game_move.move_type {
+                MoveType::Suggest { suggestion } => Some((game_move.author.clone(), suggestion)),
+                _ => None,
+            })
+            .collect();
+        let key = (
+            game.player_1.clone(),
+            game.player_2.clone(),
+            game.created_at,
+        );
+        self.suggestions.insert(key, numbers);
+    }
+
+    fn choose(
+        &mut self,
+        game: &Game,
+        state: &GameState,
+        legal_moves: &[MoveType],
+    ) -> Option<MoveType> {
+        let mut rng = rand::thread_rng();
+        if legal_moves.contains(&MoveType::RefuseSwap {}) {
+            return Some(MoveType::RefuseSwap {});
+        }
+        let predictions: Vec<usize> = legal_moves
+            .iter()
+            .filter_map(|legal_move| match legal_move {
+                MoveType::Predict { prediction } => Some(*prediction),
+                _ => None,
+            })
+            .collect();
+        if !predictions.is_empty() {
+            let opponent = game.opponent_of(&state.next_to_move(game)?);
+            let prediction = self
+                .most_suggested_by(&opponent, &predictions)
+                .or_else(|| predictions.choose(&mut rng).cloned());
+            return prediction.map(|prediction| MoveType::Predict { prediction });
+        }
+        let suggestions: Vec<usize> = legal_moves
+            .iter()
+            .filter_map(|legal_move| match legal_move {
+                MoveType::Suggest { suggestion } => Some(*suggestion),
+                _ => None,
+            })
+            .collect();
+        suggestions
+            .choose(&mut rng)
+            .map(|suggestion| MoveType::Suggest {
+                suggestion: *suggestion,
+            })
+            .or_else(|| legal_moves.choose(&mut rng).cloned())
+    }
+}
@@ -0,0 +1,20 @@
+use rules::{Game, GameState, MoveType};
+
+use crate::{advantage, best_move, play, Strategy};
+
+/// The move that leaves the player furthest ahead straight away, without looking any further
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn choose(
+        &mut self,
+        game: &Game,
+        state: &GameState,
+        legal_moves: &[MoveType],
+    ) -> Option<MoveType> {
+        let player = state.next_to_move(game)?;
+        best_move(legal_moves, |legal_move| {
+            advantage(game, &play(game, state, legal_move), &player)
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use rules::{Address, Game, GameResult, GameState};
+
+use crate::{play, Strategy};
+
+/// A game that goes on for this many moves is taken to never end
+const MAX_MOVES: usize = 1000;
+
+/// How two strategies did against each other over a number of games
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchReport {
+    pub first_wins: usize,
+    pub second_wins: usize,
+    pub draws: usize,
+}
+
+/// Play one game to the end, checking every move the strategies make against the rules
+pub fn play_game<'a>(
+    game: &Game,
+    player_1: &'a mut dyn Strategy,
+    player_2: &'a mut dyn Strategy,
+) -> Result<GameState, String> {
+    let game_address = Address::from(String::new());
+    let mut state = GameState::initial();
+    while let Some(mover) = state.next_to_move(game) {
+        if state.moves.len() >= MAX_MOVES {
+            return Err(format!("The game was not over after {} moves", MAX_MOVES));
+        }
+        let strategy = if mover == game.player_1 {
+            &mut *player_1
+        } else {
+            &mut *player_2
+        };
+        strategy.observe(game, &state);
+        let legal_moves = state.legal_moves(&game_address, game);
+        let move_type = strategy
+            .choose(game, &state, &legal_moves)
+            .ok_or_else(|| format!("{} did not make a move", mover))?;
+        if !legal_moves.contains(&move_type) {
+            return Err(format!("{} made the illegal move {:?}", mover, move_type));
+        }
+        state = play(game, &state, &move_type);
+    }
+    Ok(state)
+}
+
+/// Play the given number of games between two strategies. They take turns at being player 2 (who starts) and
+/// every game is played with the suggestion range and swap rules of the given game.
+pub fn play_match(
+    rules: &Game,
+    first: &mut dyn Strategy,
+    second: &mut dyn Strategy,
+    games: usize,
+) -> Result<MatchReport, String> {
+    let first_player = Address::from("first".to_string());
+    let second_player = Address::from("second".to_string());
+    let mut report = MatchReport::default();
+    for round in 0..games {
+        let (player_1, player_2) = if round % 2 == 0 {
+            (first_player.clone(), second_player.clone())
+        } else {
+            (second_player.clone(), first_player.clone())
+        };
+        let game = Game {
+            player_1,
+            player_2,
+            created_at: round as u32,
+            ..rules.clone()
+        };
+        let state = if game.player_1 == first_player {
+            play_game(&game, first, second)?
+        } else {
+            play_game(&game, second, first)?
+        };
+        match state.result(&game) {
+            Some(GameResult::Winner(ref winner)) if *winner == first_player => {
+                report.first_wins += 1
+            }
+            Some(GameResult::Winner(_)) => report.second_wins += 1,
+            _ => report.draws += 1,
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,96 @@
+/**
+ * Strategies pick a move for whoever is to move in a game, using nothing but the rules crate. The same strategies
+ * can play on a conductor (see the bot in the CLI) or against each other locally with the harness.
+ */
+use rand::seq::SliceRandom;
+use rules::{Address, Game, GameState, Move, MoveType};
+
+pub mod frequency;
+pub mod greedy;
+pub mod harness;
+pub mod minimax;
+pub mod random;
+
+pub use self::{
+    frequency::FrequencyStrategy, greedy::GreedyStrategy, minimax::MinimaxStrategy,
+    random::RandomStrategy,
+};
+
+pub trait Strategy {
+    /// Called with the latest state of a game before the strategy is asked to move in it, for strategies that learn
+    fn observe(&mut self, _game: &Game, _state: &GameState) {}
+
+    /// One of the legal moves for the player to move, or None to pass
+    fn choose(
+        &mut self,
+        game: &Game,
+        state: &GameState,
+        legal_moves: &[MoveType],
+    ) -> Option<MoveType>;
+}
+
+/// The names the strategies can be picked by
+pub const STRATEGY_NAMES: &[&str] = &["random", "greedy", "minimax", "frequency"];
+
+pub fn named(name: &str) -> Result<Box<dyn Strategy>, String> {
+    match name {
+        "random" => Ok(Box::new(RandomStrategy)),
+        "greedy" => Ok(Box::new(GreedyStrategy)),
+        "minimax" => Ok(Box::new(MinimaxStrategy::default())),
+        "frequency" => Ok(Box::new(FrequencyStrategy::default())),
+        _ => Err(format!(
+            "Unknown strategy {}, choose from {}",
+            name,
+            STRATEGY_NAMES.join(", ")
+        )),
+    }
+}
+
+/// The state after the player to move makes the given move. The move is assumed to be legal.
+pub fn play(game: &Game, state: &GameState, move_type: &MoveType) -> GameState {
+    let author = state
+        .next_to_move(game)
+        .unwrap_or_else(|| game.player_2.clone());
+    let new_move = Move {
+        game: Address::from(String::new()),
+        author,
+        move_type: move_type.clone(),
+        previous_move: Address::from(String::new()),
+        timestamp: state.moves.len() as u32,
+    };
+    state.evolve(game.clone(), &new_move)
+}
+
+/// How far ahead the given player is, with a finished game worth more than any lead
+pub fn advantage(game: &Game, state: &GameState, player: &Address) -> isize {
+    let lead = state.player_1_score() as isize - state.player_2_score() as isize;
+    let lead = if *player == game.player_1 {
+        lead
+    } else {
+        -lead
+    };
+    match state.result(game) {
+        Some(_) => lead * 1000,
+        None => lead,
+    }
+}
+
+/// The legal move with the highest value, picking at random between moves that are equally good
+pub fn best_move<F>(legal_moves: &[MoveType], mut value: F) -> Option<MoveType>
+where
+    F: FnMut(&MoveType) -> isize,
+{
+    let valued: Vec<(isize, &MoveType)> = legal_moves
+        .iter()
+        .map(|legal_move| (value(legal_move), legal_move))
+        .collect();
+    let best = valued.iter().map(|(value, _)| *value).max()?;
+    let best_moves: Vec<&MoveType> = valued
+        .into_iter()
+        .filter(|(value, _)| *value == best)
+        .map(|(_, legal_move)| legal_move)
+        .collect();
+    best_moves
+        .choose(&mut rand::thread_rng())
+        .map(|best_move| (*best_move).clone())
+}
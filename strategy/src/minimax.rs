@@ -0,0 +1,52 @@
+use rules::{Address, Game, GameState, MoveType};
+
+use crate::{advantage, best_move, play, Strategy};
+
+/// Looks the given number of moves ahead in a game of suggest and predict, the only game in this repository, assuming
+/// the opponent always answers with their best move. Positions are valued by the score, see `advantage`.
+/// Turns don't strictly alternate (a player can swap and then suggest) so each position is valued from the point of
+/// view of whoever is to move in it.
+pub struct MinimaxStrategy {
+    pub depth: usize,
+}
+
+impl Default for MinimaxStrategy {
+    /// Deep enough to see a swap pay off over the round that follows it
+    fn default() -> Self {
+        MinimaxStrategy { depth: 3 }
+    }
+}
+
+impl MinimaxStrategy {
+    fn value(&self, game: &Game, state: &GameState, player: &Address, depth: usize) -> isize {
+        let mover = match state.next_to_move(game) {
+            Some(mover) if depth > 0 => mover,
+            _ => return advantage(game, state, player),
+        };
+        let values = state
+            .legal_moves(&Address::from(String::new()), game)
+            .into_iter()
+            .map(|legal_move| self.value(game, &play(game, state, &legal_move), player, depth - 1));
+        let value = if mover == *player {
+            values.max()
+        } else {
+            values.min()
+        };
+        value.unwrap_or_else(|| advantage(game, state, player))
+    }
+}
+
+impl Strategy for MinimaxStrategy {
+    fn choose(
+        &mut self,
+        game: &Game,
+        state: &GameState,
+        legal_moves: &[MoveType],
+    ) -> Option<MoveType> {
+        let player = state.next_to_move(game)?;
+        let depth = self.depth.max(1) - 1;
+        best_move(legal_moves, |legal_move| {
+            self.value(game, &play(game, state, legal_move), &player, depth)
+        })
+    }
+}
@@ -0,0 +1,18 @@
+use rand::seq::SliceRandom;
+use rules::{Game, GameState, MoveType};
+
+use crate::Strategy;
+
+/// Any legal move, swaps included
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn choose(
+        &mut self,
+        _game: &Game,
+        _state: &GameState,
+        legal_moves: &[MoveType],
+    ) -> Option<MoveType> {
+        legal_moves.choose(&mut rand::thread_rng()).cloned()
+    }
+}
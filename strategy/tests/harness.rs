@@ -0,0 +1,55 @@
+/**
+ * The harness doubles as a regression test for the rules: strategies only ever see the moves the rules list as legal
+ * and the harness checks each move they pick, so a game that can't be played to the end is a bug in the rules.
+ */
+use rules::{Game, SuggestionRange, SwapRules, ROUNDS_PER_GAME};
+use strategy::harness::{play_match, MatchReport};
+use strategy::{GreedyStrategy, MinimaxStrategy, RandomStrategy, Strategy};
+
+fn rules(require_consent: bool) -> Game {
+    Game {
+        player_1: String::new(),
+        player_2: String::new(),
+        created_at: 0,
+        private: false,
+        rematch_of: None,
+        in_match: None,
+        suggestion_range: Some(SuggestionRange::default()),
+        swap_rules: Some(SwapRules {
+            max_swaps: 2,
+            require_consent,
+        }),
+        rounds: Some(ROUNDS_PER_GAME),
+    }
+}
+
+fn play(first: &mut dyn Strategy, second: &mut dyn Strategy, games: usize) -> MatchReport {
+    let report = play_match(&rules(false), first, second, games).unwrap();
+    assert_eq!(report.first_wins + report.second_wins + report.draws, games);
+    report
+}
+
+#[test]
+fn random_games_are_played_to_the_end_without_illegal_moves() {
+    for require_consent in &[false, true] {
+        let report = play_match(
+            &rules(*require_consent),
+            &mut RandomStrategy,
+            &mut RandomStrategy,
+            300,
+        );
+        assert!(report.is_ok(), "{:?}", report);
+    }
+}
+
+#[test]
+fn greedy_beats_random() {
+    let report = play(&mut GreedyStrategy, &mut RandomStrategy, 100);
+    assert!(report.first_wins > report.second_wins, "{:?}", report);
+}
+
+#[test]
+fn minimax_beats_random() {
+    let report = play(&mut MinimaxStrategy::default(), &mut RandomStrategy, 30);
+    assert!(report.first_wins > report.second_wins, "{:?}", report);
+}
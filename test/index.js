@@ -18,7 +18,8 @@ const dna = Diorama.dna(dnaPath, "matchmaker-tats");
 const diorama = new Diorama({
   instances: {
     alice: dna,
-    bob: dna
+    bob: dna,
+    carol: dna
   },
   bridges: [],
   debugLog: false,
@@ -61,4 +62,586 @@ diorama.registerScenario(
   }
 );
 
+diorama.registerScenario(
+  "Rejects numbers outside the suggestion range of the game",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0,
+      suggestion_range: { min: 1, max: 5 }
+    });
+    const game = create_game_result.Ok;
+
+    const valid_moves = await bob.callSync("main", "get_valid_moves", {
+      game_address: game
+    });
+    // bob starts so he can only suggest, or swap so that alice suggests first
+    t.deepEqual(valid_moves.Ok.map(schema => schema.kind), ["Suggest", "Swap"]);
+    t.equal(valid_moves.Ok[0].properties.suggestion.minimum, 1);
+    t.equal(valid_moves.Ok[0].properties.suggestion.maximum, 5);
+
+    const legal_moves = await bob.callSync("main", "get_legal_moves", {
+      game_address: game
+    });
+    t.equal(legal_moves.Ok.length, 6);
+    t.deepEqual(legal_moves.Ok[4], { Suggest: { suggestion: 5 } });
+
+    const check = await bob.callSync("main", "validate_move", {
+      new_move: {
+        game,
+        move_type: { Suggest: { suggestion: 6 } },
+        timestamp: 1
+      }
+    });
+    t.equal(check.Ok.valid, false);
+    t.equal(check.Ok.reason, "Numbers in this game must be between 1 and 5");
+
+    const out_of_range = await bob.callSync("main", "make_move", {
+      new_move: {
+        game,
+        move_type: { Suggest: { suggestion: 6 } },
+        timestamp: 1
+      }
+    });
+    t.notEqual(out_of_range.Err, undefined);
+
+    const in_range = await bob.callSync("main", "make_move", {
+      new_move: {
+        game,
+        move_type: { Suggest: { suggestion: 5 } },
+        timestamp: 2
+      }
+    });
+    t.equal(in_range.Err, undefined);
+
+    // the legal moves of a game are listed so its range has to stay small
+    const too_wide = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 1,
+      suggestion_range: { min: 0, max: 4294967295 }
+    });
+    t.notEqual(too_wide.Err, undefined);
+  }
+);
+
+diorama.registerScenario(
+  "Games awaiting a move follow whose turn it is",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    const game = create_game_result.Ok;
+
+    const next_result = await alice.callSync("main", "next_to_move", {
+      game_address: game
+    });
+    t.equal(next_result.Ok, bob.agentId);
+
+    const bob_awaiting = await bob.callSync("main", "games_awaiting_my_move", {});
+    t.deepEqual(bob_awaiting.Ok, [game]);
+    const alice_awaiting = await alice.callSync("main", "games_awaiting_my_move", {});
+    t.deepEqual(alice_awaiting.Ok, []);
+
+    await bob.callSync("main", "make_move", {
+      new_move: {
+        game,
+        move_type: { Suggest: { suggestion: 5 } },
+        timestamp: 1
+      }
+    });
+
+    const alice_awaiting_after = await alice.callSync("main", "games_awaiting_my_move", {});
+    t.deepEqual(alice_awaiting_after.Ok, [game]);
+  }
+);
+
+diorama.registerScenario(
+  "Earlier states of a game can be looked up by move number",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    const game = create_game_result.Ok;
+
+    await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: { Suggest: { suggestion: 3 } }, timestamp: 1 }
+    });
+    await alice.callSync("main", "make_move", {
+      new_move: { game, move_type: { Predict: { prediction: 3 } }, timestamp: 2 }
+    });
+
+    const history = await alice.callSync("main", "get_state_history", {
+      game_address: game
+    });
+    t.equal(history.Ok.length, 3);
+    t.equal(history.Ok[0].moves.length, 0);
+    t.equal(history.Ok[2].player_1_successful_prediction, 1);
+
+    const after_suggestion = await alice.callSync("main", "get_state_at", {
+      game_address: game,
+      move_index: 1
+    });
+    t.deepEqual(after_suggestion.Ok, history.Ok[1]);
+    t.equal(after_suggestion.Ok.suggestion, 3);
+
+    const too_far = await alice.callSync("main", "get_state_at", {
+      game_address: game,
+      move_index: 3
+    });
+    t.notEqual(too_far.Err, undefined);
+  }
+);
+
+diorama.registerScenario(
+  "Players can chat in a game",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    const game = create_game_result.Ok;
+
+    const send_result = await bob.callSync("main", "send_chat", {
+      game_address: game,
+      message: "good luck",
+      timestamp: 10
+    });
+    t.equal(send_result.Err, undefined);
+    await alice.callSync("main", "send_chat", {
+      game_address: game,
+      message: "you too",
+      timestamp: 20
+    });
+
+    const all_chat = await alice.callSync("main", "get_chat", {
+      game_address: game,
+      since: 0
+    });
+    t.deepEqual(all_chat.Ok.map(m => m.message), ["good luck", "you too"]);
+
+    const recent_chat = await alice.callSync("main", "get_chat", {
+      game_address: game,
+      since: 15
+    });
+    t.deepEqual(recent_chat.Ok.map(m => m.message), ["you too"]);
+  }
+);
+
+diorama.registerScenario(
+  "Only the players can see the moves of a private game",
+  async (s, t, { alice, bob, carol }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0,
+      private: true
+    });
+    const game = create_game_result.Ok;
+
+    const suggest_result = await bob.callSync("main", "make_move", {
+      new_move: { game, move_type: { Suggest: { suggestion: 4 } }, timestamp: 1 }
+    });
+    t.equal(suggest_result.Err, undefined);
+    const predict_result = await alice.callSync("main", "make_move", {
+      new_move: { game, move_type: { Predict: { prediction: 4 } }, timestamp: 2 }
+    });
+    t.equal(predict_result.Err, undefined);
+
+    const alice_state = await alice.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.deepEqual(
+      alice_state.Ok.moves.map(m => m.move_type),
+      [{ Suggest: { suggestion: 4 } }, { Predict: { prediction: 4 } }]
+    );
+    t.equal(alice_state.Ok.player_1_successful_prediction, 1);
+
+    const bob_state = await bob.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.deepEqual(bob_state.Ok, alice_state.Ok);
+
+    // the game itself is public but a spectator can't get the moves
+    const carol_game = await carol.callSync("main", "get_game", {
+      game_address: game
+    });
+    t.equal(carol_game.Ok.private, true);
+    const carol_state = await carol.callSync("main", "get_state", {
+      game_address: game
+    });
+    t.notEqual(carol_state.Err, undefined);
+  }
+);
+
+diorama.registerScenario(
+  "Can rematch a finished game with the roles swapped",
+  async (s, t, { alice, bob }) => {
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    const game = create_game_result.Ok;
+
+    // bob always suggests and alice always predicts correctly for the 10 rounds
+    for (let round = 0; round < 10; round++) {
+      await bob.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Suggest: { suggestion: 3 } },
+          timestamp: round * 2 + 1
+        }
+      });
+      await alice.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Predict: { prediction: 3 } },
+          timestamp: round * 2 + 2
+        }
+      });
+    }
+
+    const result = await alice.callSync("main", "get_result", {
+      game_address: game
+    });
+    t.deepEqual(result.Ok, { Winner: alice.agentId });
+
+    const record = await bob.callSync("main", "export_game", {
+      game_address: game,
+      format: "Pgn"
+    });
+    t.ok(record.Ok.includes('[Result "1-0"]'));
+    t.ok(record.Ok.includes("20. P1 Predict 3 {20}"));
+
+    const request_result = await alice.callSync("main", "request_rematch", {
+      game_address: game,
+      created_at: 100
+    });
+    t.equal(request_result.Err, undefined);
+
+    const accept_result = await bob.callSync("main", "accept_rematch", {
+      request_addr: request_result.Ok,
+      created_at: 101
+    });
+    t.equal(accept_result.Err, undefined);
+
+    // a game only has one rematch, so the series can't branch
+    const second_accept = await bob.callSync("main", "accept_rematch", {
+      request_addr: request_result.Ok,
+      created_at: 102
+    });
+    t.notEqual(second_accept.Err, undefined);
+
+    const next_result = await bob.callSync("main", "next_to_move", {
+      game_address: accept_result.Ok
+    });
+    t.equal(next_result.Ok, alice.agentId);
+
+    const series = await bob.callSync("main", "get_series", {
+      game_address: accept_result.Ok
+    });
+    t.deepEqual(series.Ok.games, [game, accept_result.Ok]);
+    t.equal(series.Ok.player_1_wins, 1);
+    t.equal(series.Ok.unfinished, 1);
+  }
+);
+
+diorama.registerScenario(
+  "Can report a game to a single elimination tournament",
+  async (s, t, { alice, bob, carol }) => {
+    const create_result = await alice.callSync("main", "create_tournament", {
+      name: "final",
+      format: "SingleElimination",
+      player_cap: 3,
+      created_at: 0
+    });
+    const tournament = create_result.Ok;
+    await alice.callSync("main", "register", { tournament_address: tournament });
+    await bob.callSync("main", "register", { tournament_address: tournament });
+
+    // the pairings are only made once the organiser starts the tournament
+    const open_bracket = await bob.callSync("main", "get_bracket", {
+      tournament_address: tournament
+    });
+    t.equal(open_bracket.Ok.started, false);
+    t.equal(open_bracket.Ok.rounds.length, 0);
+
+    const bob_start = await bob.callSync("main", "start_tournament", {
+      tournament_address: tournament,
+      started_at: 1
+    });
+    t.notEqual(bob_start.Err, undefined);
+    const start = await alice.callSync("main", "start_tournament", {
+      tournament_address: tournament,
+      started_at: 1
+    });
+    t.equal(start.Err, undefined);
+
+    const bracket = await bob.callSync("main", "get_bracket", {
+      tournament_address: tournament
+    });
+    t.equal(bracket.Ok.rounds.length, 1);
+    t.equal(bracket.Ok.current_round, 0);
+
+    // registering after the start would reshuffle the pairings
+    const late_register = await carol.callSync("main", "register", {
+      tournament_address: tournament
+    });
+    t.notEqual(late_register.Err, undefined);
+    const same_bracket = await alice.callSync("main", "get_bracket", {
+      tournament_address: tournament
+    });
+    t.deepEqual(same_bracket.Ok.players, bracket.Ok.players);
+
+    const pairing = bracket.Ok.rounds[0][0];
+    const create_game_result = await alice.callSync("main", "create_game", {
+      opponent: pairing.player_1 === alice.agentId ? pairing.player_2 : pairing.player_1,
+      timestamp: 1
+    });
+    const game = create_game_result.Ok;
+
+    // an unfinished game cannot be reported
+    const early_report = await alice.callSync("main", "report_result", {
+      tournament_address: tournament,
+      game_address: game
+    });
+    t.notEqual(early_report.Err, undefined);
+
+    for (let round = 0; round < 10; round++) {
+      await bob.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Suggest: { suggestion: 3 } },
+          timestamp: round * 2 + 2
+        }
+      });
+      await alice.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Predict: { prediction: 3 } },
+          timestamp: round * 2 + 3
+        }
+      });
+    }
+
+    const report = await alice.callSync("main", "report_result", {
+      tournament_address: tournament,
+      game_address: game
+    });
+    t.equal(report.Err, undefined);
+
+    const second_report = await bob.callSync("main", "report_result", {
+      tournament_address: tournament,
+      game_address: game
+    });
+    t.notEqual(second_report.Err, undefined);
+
+    const final_bracket = await bob.callSync("main", "get_bracket", {
+      tournament_address: tournament
+    });
+    t.equal(final_bracket.Ok.current_round, null);
+    t.equal(final_bracket.Ok.winner, alice.agentId);
+  }
+);
+
+diorama.registerScenario(
+  "Can start and advance a match",
+  async (s, t, { alice, bob }) => {
+    const create_result = await alice.callSync("main", "create_match", {
+      opponent: bob.agentId,
+      target_wins: 1,
+      created_at: 0
+    });
+    const game_match = create_result.Ok;
+
+    const start_result = await bob.callSync("main", "start_match_game", {
+      match_address: game_match,
+      created_at: 1
+    });
+    t.equal(start_result.Err, undefined);
+    const game = start_result.Ok;
+
+    // only one game of a match is played at a time
+    const early_start = await alice.callSync("main", "start_match_game", {
+      match_address: game_match,
+      created_at: 2
+    });
+    t.notEqual(early_start.Err, undefined);
+
+    const state = await alice.callSync("main", "get_match_state", {
+      match_address: game_match
+    });
+    t.deepEqual(state.Ok.match.games, [game]);
+    t.equal(state.Ok.current_game, game);
+
+    // alice is player 1 of the first game so bob suggests and she predicts correctly every round
+    for (let round = 0; round < 10; round++) {
+      await bob.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Suggest: { suggestion: 3 } },
+          timestamp: round * 2 + 3
+        }
+      });
+      await alice.callSync("main", "make_move", {
+        new_move: {
+          game,
+          move_type: { Predict: { prediction: 3 } },
+          timestamp: round * 2 + 4
+        }
+      });
+    }
+
+    const final_state = await bob.callSync("main", "get_match_state", {
+      match_address: game_match
+    });
+    t.equal(final_state.Ok.winner, alice.agentId);
+    t.equal(final_state.Ok.current_game, null);
+
+    const late_start = await bob.callSync("main", "start_match_game", {
+      match_address: game_match,
+      created_at: 30
+    });
+    t.notEqual(late_start.Err, undefined);
+  }
+);
+
+diorama.registerScenario(
+  "The leaderboard ranks players by wins then draws and keeps the best",
+  async (s, t, { alice, bob, carol }) => {
+    // bob suggests every round and the predictor guesses right in the rounds listed
+    const play = async (predictor, game, correct_rounds) => {
+      for (let round = 0; round < 10; round++) {
+        await bob.callSync("main", "make_move", {
+          new_move: {
+            game,
+            move_type: { Suggest: { suggestion: 3 } },
+            timestamp: round * 2 + 1
+          }
+        });
+        await predictor.callSync("main", "make_move", {
+          new_move: {
+            game,
+            move_type: {
+              Predict: { prediction: correct_rounds.includes(round) ? 3 : 4 }
+            },
+            timestamp: round * 2 + 2
+          }
+        });
+      }
+    };
+
+    const alice_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    await play(alice, alice_game.Ok, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    const carol_game = await carol.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    await play(carol, carol_game.Ok, [0, 1, 2, 3, 4]);
+    // unfinished games don't count
+    const unfinished_game = await alice.callSync("main", "create_game", {
+      opponent: carol.agentId,
+      timestamp: 1
+    });
+
+    // the finished games are no longer live
+    const live_games = await bob.callSync("main", "list_live_games", {});
+    t.deepEqual(live_games.Ok.map(response => response.address), [
+      unfinished_game.Ok
+    ]);
+
+    const leaderboard = await carol.callSync("main", "get_leaderboard", {
+      game_type: "suggest_predict",
+      limit: 10
+    });
+    t.deepEqual(leaderboard.Ok.map(standing => standing.agent), [
+      alice.agentId,
+      carol.agentId,
+      bob.agentId
+    ]);
+    t.deepEqual(
+      leaderboard.Ok.map(standing => [
+        standing.wins,
+        standing.draws,
+        standing.losses
+      ]),
+      [[1, 0, 0], [0, 1, 0], [0, 1, 1]]
+    );
+    t.equal(leaderboard.Ok[1].prediction_rate, 0.5);
+
+    const top_two = await bob.callSync("main", "get_leaderboard", {
+      game_type: "suggest_predict",
+      limit: 2
+    });
+    t.deepEqual(top_two.Ok, leaderboard.Ok.slice(0, 2));
+  }
+);
+
+diorama.registerScenario(
+  "Player stats only count the games a player has moved in",
+  async (s, t, { alice, bob, carol }) => {
+    const short_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 0
+    });
+    await bob.callSync("main", "make_move", {
+      new_move: {
+        game: short_game.Ok,
+        move_type: { Suggest: { suggestion: 2 } },
+        timestamp: 1
+      }
+    });
+    await alice.callSync("main", "make_move", {
+      new_move: {
+        game: short_game.Ok,
+        move_type: { Predict: { prediction: 2 } },
+        timestamp: 2
+      }
+    });
+    // only bob has moved in this one
+    const waiting_game = await alice.callSync("main", "create_game", {
+      opponent: bob.agentId,
+      timestamp: 3
+    });
+    await bob.callSync("main", "make_move", {
+      new_move: {
+        game: waiting_game.Ok,
+        move_type: { Suggest: { suggestion: 5 } },
+        timestamp: 4
+      }
+    });
+    // and nobody has moved in this one
+    await carol.callSync("main", "create_game", {
+      opponent: alice.agentId,
+      timestamp: 5
+    });
+
+    const alice_stats = await carol.callSync("main", "get_player_stats", {
+      agent: alice.agentId
+    });
+    t.equal(alice_stats.Ok.games_played, 1);
+    t.equal(alice_stats.Ok.prediction_attempts, 1);
+    t.equal(alice_stats.Ok.successful_predictions, 1);
+    t.equal(alice_stats.Ok.prediction_accuracy, 1);
+
+    const bob_stats = await carol.callSync("main", "get_player_stats", {
+      agent: bob.agentId
+    });
+    t.equal(bob_stats.Ok.games_played, 2);
+    t.equal(bob_stats.Ok.suggestion_attempts, 2);
+    t.deepEqual(bob_stats.Ok.favourite_suggestions, [
+      { suggestion: 2, count: 1 },
+      { suggestion: 5, count: 1 }
+    ]);
+
+    const carol_stats = await carol.callSync("main", "get_player_stats", {
+      agent: carol.agentId
+    });
+    t.equal(carol_stats.Ok.games_played, 0);
+  }
+);
+
 diorama.run();
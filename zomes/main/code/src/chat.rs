@@ -0,0 +1,121 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_core_types::{
+        dna::entry_types::Sharing,
+        entry::Entry,
+        link::LinkMatch,
+        validation::{EntryValidationData, LinkValidationData},
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+    utils, AGENT_ADDRESS,
+};
+
+use crate::game::{self, Game};
+use crate::messaging::{self, DirectMessage};
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct ChatMessage {
+    pub game: Address,
+    pub author: Address,
+    pub message: String,
+    pub timestamp: u32,
+}
+
+pub fn handle_send_chat(
+    game_address: Address,
+    message: String,
+    timestamp: u32,
+) -> ZomeApiResult<Address> {
+    let game = game::get_game(&game_address)?;
+    let chat_message = ChatMessage {
+        game: game_address.clone(),
+        author: AGENT_ADDRESS.to_string().into(),
+        message,
+        timestamp,
+    };
+    let entry = Entry::App("chat_message".into(), chat_message.clone().into());
+    let message_address = hdk::commit_entry(&entry)?;
+    hdk::link_entries(&game_address, &message_address, "has_chat_message", "")?;
+
+    messaging::notify(
+        &game.opponent_of(&chat_message.author),
+        DirectMessage::ChatReceived {
+            game: game_address,
+            chat_message,
+        },
+    )?;
+    Ok(message_address)
+}
+
+/// Chat messages for a game sent at or after the given timestamp, oldest first
+pub fn handle_get_chat(game_address: Address, since: u32) -> ZomeApiResult<Vec<ChatMessage>> {
+    let mut messages: Vec<ChatMessage> = utils::get_links_and_load_type(
+        &game_address,
+        LinkMatch::Exactly("has_chat_message"),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .filter(|chat_message: &ChatMessage| chat_message.timestamp >= since)
+    .collect();
+    messages.sort_by_key(|chat_message| chat_message.timestamp);
+    Ok(messages)
+}
+
+pub fn chat_message_def() -> ValidatingEntryType {
+    entry!(
+        name: "chat_message",
+        description: "A message sent by one of the players of a game to the other",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<ChatMessage>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let chat_message = ChatMessage::from(entry);
+                    if !validation_data.sources().contains(&chat_message.author) {
+                        return Err("Cannot author a chat message from another agent".into());
+                    }
+                    let game: Game = utils::get_as_type(chat_message.game.clone())?;
+                    if chat_message.author != game.player_1 && chat_message.author != game.player_2 {
+                        return Err("Only the players of a game can chat in it".into());
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a chat message".into())
+                }
+            }
+        },
+        links: [
+            from!(
+                "game",
+                link_type: "has_chat_message",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{link, ..} => {
+                            // a message can only be linked from the game it was sent in, by one of its players
+                            let chat_message: ChatMessage = utils::get_as_type(link.link.target().clone())?;
+                            if chat_message.game != *link.link.base() {
+                                return Err("A chat message can only be linked from its own game".into());
+                            }
+                            let game: Game = utils::get_as_type(link.link.base().clone())?;
+                            if chat_message.author != game.player_1 && chat_message.author != game.player_2 {
+                                return Err("Only the players of a game can chat in it".into());
+                            }
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove a chat message".into())
+                        }
+                    }
+                }
+            )
+        ]
+    )
+}
@@ -0,0 +1,39 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use rules::{GameRecord, GAME_TYPE};
+
+use crate::game;
+
+/**
+ *
+ * A finished (or unfinished) game can be exported as a PGN style text record, a block of [Tag "value"] headers
+ * followed by the numbered moves, or as JSON holding the same information. Both can be replayed offline with the rules crate.
+ *
+ */
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ExportFormat {
+    Pgn,
+    Json,
+}
+
+pub fn handle_export_game(game_address: Address, format: ExportFormat) -> ZomeApiResult<String> {
+    let game = game::get_game(&game_address)?;
+    let state = game::get_state(&game_address)?;
+    let record = GameRecord {
+        address: game_address,
+        game_type: GAME_TYPE.into(),
+        result: state.result(&game),
+        game,
+        moves: state.moves,
+    };
+    match format {
+        ExportFormat::Pgn => Ok(record.to_pgn()),
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(&record).map_err(|e| ZomeApiError::Internal(e.to_string()))
+        }
+    }
+}
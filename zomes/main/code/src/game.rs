@@ -2,118 +2,276 @@ use hdk::{
     entry_definition::ValidatingEntryType,
     error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{
-        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
+        dna::entry_types::Sharing,
+        entry::Entry,
+        link::LinkMatch,
+        validation::{EntryValidationData, LinkValidationData},
     },
-    holochain_json_api::{error::JsonError, json::JsonString},
     holochain_persistence_api::cas::content::{Address, AddressableContent},
-    utils,
+    utils, AGENT_ADDRESS,
 };
 use std::convert::TryFrom;
 
-use crate::game_move::Move;
-use crate::GameState;
+use crate::game_match::Match;
+use crate::game_move::{get_cached_moves, CachedMove, Move};
+use crate::leaderboard;
+use crate::matchmaking::GetResponse;
+use crate::messaging;
+use crate::private_move::{self, SealedMove};
+use crate::{GameResult, GameState};
+use rules::MAX_RANGE_WIDTH;
 
-#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
-pub struct Game {
-    pub player_1: Address,
-    pub player_2: Address,
-    pub created_at: u32,
+pub use rules::Game;
+
+/// Wins, draws and unfinished games across several games between the same two players
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct ResultTally {
+    pub player_1_wins: usize,
+    pub player_2_wins: usize,
+    pub draws: usize,
+    pub unfinished: usize,
 }
 
 /*=====================================
 =            DHT Functions            =
 =====================================*/
 
-/// Traverse the linked list rooted at a game to find all the moves
-pub fn get_moves(game_address: &Address) -> ZomeApiResult<Vec<Move>> {
-    match hdk::get_links(game_address, LinkMatch::Any, LinkMatch::Any)?
+/// Commit a new game and link it from both players so each of them can find it later.
+/// It is also linked from the live games anchor so others can find it to spectate, and added to the leaderboard.
+pub fn commit_game(game: Game) -> ZomeApiResult<Address> {
+    let game_entry = Entry::App("game".into(), game.clone().into());
+    let game_address = hdk::commit_entry(&game_entry)?;
+    hdk::link_entries(&game.player_1, &game_address, "has_game", "")?;
+    hdk::link_entries(&game.player_2, &game_address, "has_game", "")?;
+
+    let anchor_address = hdk::commit_entry(&live_games_anchor())?;
+    hdk::link_entries(&anchor_address, &game_address, "live_game", "")?;
+    leaderboard::add_game(&game, &game_address)?;
+    Ok(game_address)
+}
+
+fn live_games_anchor() -> Entry {
+    Entry::App("anchor".into(), "live_games".into())
+}
+
+/// Take a finished game off the live games, called by whoever made its last move
+pub fn end_live_game(game_address: &Address) -> ZomeApiResult<()> {
+    hdk::remove_link(
+        &live_games_anchor().address(),
+        game_address,
+        "live_game",
+        "",
+    )
+}
+
+/// All the games that are still being played, for spectators to pick from.
+/// Games leave the anchor when they finish so nothing has to be replayed to list them.
+/// Private games are included even though only the players can see their moves.
+pub fn get_live_games() -> ZomeApiResult<Vec<GetResponse<Game>>> {
+    Ok(utils::get_links_and_load_type(
+        &live_games_anchor().address(),
+        LinkMatch::Exactly("live_game"),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .map(|game: Game| {
+        let address = Entry::App("game".into(), game.clone().into()).address();
+        GetResponse {
+            entry: game,
+            address,
+        }
+    })
+    .collect())
+}
+
+/// All the games an agent is playing in, following the per-agent game links
+pub fn get_agent_games(agent: &Address) -> ZomeApiResult<Vec<Address>> {
+    Ok(hdk::get_links(agent, LinkMatch::Exactly("has_game"), LinkMatch::Any)?.addresses())
+}
+
+/// Find the move that follows the given game or move address. Moves we hold locally (delivered to us directly
+/// by the opponent, or our own moves in a private game) are used if the link to them has not arrived via gossip yet.
+fn get_next_move_address(
+    game: &Game,
+    address: &Address,
+    known_moves: &[CachedMove],
+) -> ZomeApiResult<Option<Address>> {
+    // moves are linked with an empty link type (sealed_move for private games), other links (e.g. chat) hang off the game too
+    let link_type = if game.private { "sealed_move" } else { "" };
+    match hdk::get_links(address, LinkMatch::Exactly(link_type), LinkMatch::Any)?
         .addresses()
         .into_iter()
         .next()
     {
-        /* get links returns the ZomeApiResult<GetLinksResult>.
-         * This will get entries that are linked to the first argument.
-         * Since ZomeApiResult returns Result<T, ZomeApiError>(where T in this case is the GetLinksResult),
-         * you can use the ? sugar to return the ZomeApiError if error then return the T if get_links is a success.
-         * GetLinkResult has a method implemented called addresses() which returns a vector of Addresses.
-         * into_iter() will iterate through this vector of addresses and move the value from the vector to an Iterator.
-         * next() is a method for iterator where in it returns the next value of the Iterator (start at index 0) in Option<Self::Item>
-         * Since next() returns an Option<Self::Item>, we can use the match operator to cater to all possible values of Option<Self: Item>
+        Some(next_address) => Ok(Some(next_address)),
+        None => Ok(known_moves
+            .iter()
+            .find(|known| known.game_move.previous_move == *address)
+            .map(|known| known.address.clone())),
+    }
+}
+
+/// The moves of a game we already hold locally, which for a private game is the only place their contents are
+fn get_known_moves(game: &Game, game_address: &Address) -> ZomeApiResult<Vec<CachedMove>> {
+    let mut known_moves = get_cached_moves(game_address)?;
+    if game.private {
+        known_moves.extend(private_move::get_private_moves(game_address)?);
+    }
+    Ok(known_moves)
+}
+
+/// Traverse the linked list rooted at a game to find the addresses of all its moves, oldest first
+fn get_move_addresses(
+    game: &Game,
+    game_address: &Address,
+    known_moves: &[CachedMove],
+) -> ZomeApiResult<Vec<Address>> {
+    match get_next_move_address(game, game_address, known_moves)? {
+        /* get_next_move_address returns ZomeApiResult<Option<Address>>.
+         * It gets the entries that are linked to the first argument using get_links and takes the first one, or if nothing is
+         * linked yet it looks through the moves we hold locally for one that follows the given address.
+         * Since ZomeApiResult returns Result<T, ZomeApiError>(where T in this case is the Option<Address>),
+         * you can use the ? sugar to return the ZomeApiError if error then return the T if the lookup is a success.
+         * Since it returns an Option, we can use the match operator to cater to all possible values.
          */
         Some(first_move) => {
             let mut move_addresses = vec![first_move];
             let mut more = true;
             while more {
-                more = match hdk::get_links(
-                    move_addresses.last().unwrap(),
-                    LinkMatch::Any,
-                    LinkMatch::Any,
-                )?
-                .addresses()
-                .into_iter()
-                .next()
-                {
-                    Some(addr) => {
-                        move_addresses.push(addr.clone());
-                        true
+                more =
+                    match get_next_move_address(game, move_addresses.last().unwrap(), known_moves)?
+                    {
+                        Some(addr) => {
+                            move_addresses.push(addr.clone());
+                            true
+                        }
+                        None => false,
                     }
-                    None => false,
-                }
             }
             /* In this match operator, we first cater to Some(first_move). The name is first_move because
              * the Game entry is always linked to the first_move made by Player 2.
              * So we store this first_move to a vector in a variable name move_addresses.
-             * Then we create a while loop in order to store all the game_move entries that are linked to the first_move.
-             * while more is true, we get the entries linked to the first_move, then the next move and the next move and
-             * on and on and on until we finish all the linked moves. The way this works is, in the first argument of get_links,
+             * Then we create a while loop in order to store all the game_move entries that follow the first_move.
+             * while more is true, we get the move following the first_move, then the next move and the next move and
+             * on and on and on until we finish all the linked moves. The way this works is, in the first argument of get_next_move_address,
              * we get the very last element of the move_addresses vector using last() which returns a Option<&T>.
              * Since we want the address itself wrapped in Option<&T>, we will use unwrap() to get the value of the Address.
-             * In this way, we will always have the last address stored in move_addresses as our first argument in get_links.Address.
-             * Then we do the same thing we did above to move the value from a vector of addresses to an Iterator then get the value with next().
+             * In this way, we will always have the last address stored in move_addresses as our first argument.
              * Then we run the match operator again to store the address in the move_addresses using push() then return true to run the loop again.
-             * Since next() returns None if there is no more value to be retrieved in the Iterator, we return false in None so that the loop ends after
+             * Since get_next_move_address returns None if there is no following move, we return false in None so that the loop ends after
              * we get all the moves that are linked together.
              */
-            let moves: Vec<Move> = move_addresses
-                .iter()
-                .map(|addr| {
-                    let move_entry = hdk::get_entry(addr).unwrap().unwrap();
-                    if let Entry::App(_, move_struct) = move_entry {
-                        Move::try_from(move_struct)
-                            .expect("Entry at address is type other than Move")
-                    } else {
-                        panic!("Not an app entry!")
-                    }
-                })
-                .collect();
-            /* Now that we have a vector of addresses for all connected moves, we will now try to retrieve the data itself which can
-             * be found in the Addresses we retrieved. First, we create a variable named moves which is a type of Vec<Move>. In this variable,
-             * we will use the iter() method on move_addresses (note that we used iter() instead of into_iter() because we dont want
-             * to move the value from move_addresses but rather have a referennce to the addresses found in the move_addresses.) and then
-             * use map() method provided in Iterator. map() takes a closure and creates an iterator which calls that closure on each element.
-             * the closure will have addr as an argument. The closure creates a variable named move_entry in which we will use the method
-             * get_entry which takes an Address(HashString) type then return ZomeApiResult<Option<Entry>>. We then unwrap it twice to
-             * retrieve the Entry itself. Then we use if let to match the move_entry with an Entry::App variant. This is because Entry
-             * enum can have different variants and we need to makesure that the entry found in this address is an App variant. If not
-             * then we throw a panic in else statement saying that it is not an app entry. Now if it is an app entry, we use the try_from method
-             * to try to convert the Entry::App, which we assume to have the Move struct in the second element of App
-             * variant(here named as move_struct) as the AppEntryValue type, to an actual Move struct. If the try_from fails then we throw an error
-             * saying the Entry at the given address is not a Move type of entry. After we call the closure on all addresses in move_addresses,
-             * we use the collect() to turn them into Vec<Move>. collect() would understand that the items should be collected into Vec<Move>
-             * since that is the defined type for moves.
-             */
-            Ok(moves)
+            Ok(move_addresses)
         }
         None => Ok(Vec::new()),
     }
 }
 
+/// The address the next move of a game follows: its latest move, or the game itself if no moves have been made
+pub fn get_last_move_address(game_address: &Address) -> ZomeApiResult<Address> {
+    let game = get_game(game_address)?;
+    let known_moves = get_known_moves(&game, game_address)?;
+    Ok(get_move_addresses(&game, game_address, &known_moves)?
+        .pop()
+        .unwrap_or_else(|| game_address.clone()))
+}
+
+/// Traverse the linked list rooted at a game to find all the moves
+pub fn get_moves(game_address: &Address) -> ZomeApiResult<Vec<Move>> {
+    let game = get_game(game_address)?;
+    let mut known_moves = get_known_moves(&game, game_address)?;
+    let move_addresses = get_move_addresses(&game, game_address, &known_moves)?;
+    if game.private {
+        fill_private_moves(&game, game_address, &move_addresses, &mut known_moves)?;
+    }
+    let moves: Vec<Move> = move_addresses
+        .iter()
+        .map(|addr| {
+            if let Some(known) = known_moves.iter().find(|known| known.address == *addr) {
+                return known.game_move.clone();
+            }
+            let move_entry = hdk::get_entry(addr).unwrap().unwrap();
+            if let Entry::App(_, move_struct) = move_entry {
+                Move::try_from(move_struct).expect("Entry at address is type other than Move")
+            } else {
+                panic!("Not an app entry!")
+            }
+        })
+        .collect();
+    /* Now that we have a vector of addresses for all connected moves, we will now try to retrieve the data itself.
+     * Moves we already hold locally are used directly as they may not be retrievable from the DHT yet.
+     * For the others we use the method get_entry which takes an Address(HashString) type then return
+     * ZomeApiResult<Option<Entry>>. We then unwrap it twice to retrieve the Entry itself. Then we use if let to match
+     * the move_entry with an Entry::App variant. This is because Entry enum can have different variants and we need to
+     * makesure that the entry found in this address is an App variant. If not then we throw a panic in else statement
+     * saying that it is not an app entry. Now if it is an app entry, we use the try_from method to try to convert the
+     * Entry::App, which we assume to have the Move struct in the second element of App variant(here named as move_struct)
+     * as the AppEntryValue type, to an actual Move struct. If the try_from fails then we throw an error saying the Entry
+     * at the given address is not a Move type of entry. collect() would understand that the items should be collected
+     * into Vec<Move> since that is the defined type for moves.
+     */
+    Ok(moves)
+}
+
+/// Make sure we hold the contents of every sealed move in a private game, asking the opponent for any we are missing.
+/// Nobody else can check the opponent's moves, so each one must match its sealed move and be allowed by the rules.
+fn fill_private_moves(
+    game: &Game,
+    game_address: &Address,
+    move_addresses: &[Address],
+    known_moves: &mut Vec<CachedMove>,
+) -> ZomeApiResult<()> {
+    let missing = move_addresses
+        .iter()
+        .any(|addr| !known_moves.iter().any(|known| known.address == *addr));
+    if !missing {
+        return Ok(());
+    }
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    if me != game.player_1 && me != game.player_2 {
+        return Err(ZomeApiError::Internal(
+            "The moves of a private game are only visible to its players".into(),
+        ));
+    }
+    let opponent = game.opponent_of(&me);
+    for private_move in messaging::request_moves(&opponent, game_address)? {
+        // the sealed move holds the hash of the move and its nonce, so a move that was swapped for
+        // another one after it was sealed ends up at a different address and is never used
+        if private_move.game_move.author == opponent {
+            known_moves.push(CachedMove {
+                address: SealedMove::seal(&private_move).address(),
+                game_move: private_move.game_move,
+            });
+        }
+    }
+
+    let mut state = GameState::initial();
+    for addr in move_addresses {
+        let known = known_moves
+            .iter()
+            .find(|known| known.address == *addr)
+            .ok_or_else(|| {
+                ZomeApiError::Internal(
+                    "Could not get all the moves of this private game from the opponent".into(),
+                )
+            })?;
+        if known.game_move.author == opponent {
+            known
+                .game_move
+                .is_valid(game.clone(), state.clone())
+                .map_err(|e| {
+                    ZomeApiError::Internal(format!("The opponent made an invalid move: {}", e))
+                })?;
+        }
+        state = state.evolve(game.clone(), &known.game_move);
+    }
+    Ok(())
+}
+
 pub fn get_state(game_address: &Address) -> ZomeApiResult<GameState> {
     let moves = get_moves(game_address)?;
     let game = get_game(game_address)?;
-    let new_state = moves.iter().fold(GameState::initial(), |state, new_move| {
-        state.evolve(game.clone(), new_move)
-    });
+    let new_state = fold_moves(&game, &moves);
     Ok(new_state)
     /* get_state takes the address of the game as a parameter and return a ZomeApiResult<GameState>. This is a reducer function.
      * First we create a vairable named moves and call the get_moves in it with the parameter game_address.
@@ -121,17 +279,72 @@ pub fn get_state(game_address: &Address) -> ZomeApiResult<GameState> {
      * T in this case is Vec<Move> which will also be the type of moves variable. next we create the game variable an call the get_game
      * with the game_address being its argument. get_game also returns ZomeApiResult with Game being the success value so we
      * use the ? to get the Game struct if no error occurs. with moves and game having the vectors we need, we will now create
-     * a variable name new_state and call iter() on moves to turn it into an Iterator in order for us to call a method fold() on it.
+     * a variable name new_state and fold all the moves into it with fold_moves. now we can return this as Ok(new_state)
+     */
+}
+
+/// The state once the first move_index moves had been made, 0 being the state before anyone moved
+pub fn get_state_at(game_address: &Address, move_index: usize) -> ZomeApiResult<GameState> {
+    let moves = get_moves(game_address)?;
+    if move_index > moves.len() {
+        return Err(ZomeApiError::Internal(format!(
+            "The game only has {} moves",
+            moves.len()
+        )));
+    }
+    let game = get_game(game_address)?;
+    Ok(fold_moves(&game, &moves[..move_index]))
+}
+
+/// The state before any move was made followed by the state after each move, in the order they were made
+pub fn get_state_history(game_address: &Address) -> ZomeApiResult<Vec<GameState>> {
+    let moves = get_moves(game_address)?;
+    let game = get_game(game_address)?;
+    Ok(moves
+        .iter()
+        .fold(vec![GameState::initial()], |mut history, new_move| {
+            let next_state = history.last().unwrap().evolve(game.clone(), new_move);
+            history.push(next_state);
+            history
+        }))
+}
+
+fn fold_moves(game: &Game, moves: &[Move]) -> GameState {
+    moves.iter().fold(GameState::initial(), |state, new_move| {
+        state.evolve(game.clone(), new_move)
+    })
+    /* We call iter() on moves to turn it into an Iterator in order for us to call a method fold() on it.
      * fold() takes two arguments: an initial value, and a closure with two arguments: an 'accumulator', and an element.
      * The closure returns the value that the accumulator should have for the next iteration. In this case, the initial value is an empty
      * GameState created with initial() we associated with GameState. Then the accumulator will be named state which will hold the
      * initial value (empty GameState) we set. new_move will be each Move stored in moves. now we call the evolve() method we associated
      * with GameState in state.rs. evolve takes self, Game struct, and &Move so we clone game and give it as a first argument and a
      * reference to moves with new_move(automatically a reference since the element in fold has FnMut implemented). This evolve method will
-     * add all the Move that is in the moves to the GameState which will be stored in new_state. now we can return this as Ok(new_state)
+     * add all the Move that is in the moves to the GameState which is returned once every move has been applied.
      */
 }
 
+/// Add up the results of the given games. Wins by the given player count as player 1 wins,
+/// anyone else winning counts as a player 2 win, whichever role they had in each game.
+pub fn tally_results(player_1: &Address, games: &[Address]) -> ZomeApiResult<ResultTally> {
+    let mut tally = ResultTally::default();
+    for address in games {
+        let game = get_game(address)?;
+        match get_state(address)?.result(&game) {
+            Some(GameResult::Winner(winner)) => {
+                if winner == *player_1 {
+                    tally.player_1_wins += 1
+                } else {
+                    tally.player_2_wins += 1
+                }
+            }
+            Some(GameResult::Draw) => tally.draws += 1,
+            None => tally.unfinished += 1,
+        }
+    }
+    Ok(tally)
+}
+
 pub fn get_game(game_address: &Address) -> ZomeApiResult<Game> {
     utils::get_as_type(game_address.to_owned())
     /* get_as_type load an entry from the given address in the argument then convert it to a given type wrapped in ZomeApiResult. In this case,
@@ -142,101 +355,67 @@ pub fn get_game(game_address: &Address) -> ZomeApiResult<Game> {
 
 /*=====  End of DHT Functions  ======*/
 
-/*=============================================
-=            Local chain functions            =
-=============================================*/
+/*==================================================
+=            Validation package functions            =
+==================================================*/
 
-pub fn get_game_local_chain(
-    local_chain: Vec<Entry>,
-    game_address: &Address,
-) -> ZomeApiResult<Game> {
-    local_chain
-        .iter()
-        .filter(|entry| entry.address() == game_address.clone())
-        .filter_map(|entry| {
-            if let Entry::App(_, entry_data) = entry {
-                Some(Game::try_from(entry_data.clone()).unwrap())
-            } else {
-                None
-            }
-        })
-        .next()
-        .ok_or(ZomeApiError::HashNotFound)
-    /* get_game_local_chain() gets all the Entry in the local_chain as well as the address of the game and will return ZomeApiResult<Game>.
-     * now we will call the iter() method on the local_chain so that we can call the filter() method. filter() method will create an iterator
-     * which uses a closure to determine if an element should be yielded. the closure must return true or false and if the closure returns
-     * true on that element then filter() will return that element. if its a false it simply runs the same closure on the nexrt element.
-     * now filter's closure check if the address of the each element found in the local_chain is equal to the address of game_address
-     * by getting the address of each element in the localchain using address() method provided for the Entry type in hdk. we need to clone
-     * the game_address because we are passing a reference in the parameter and we cant compare a reference to an actual value(not 100% sure
-     * correct me if im wrong). If the address of the entry matches the game_address that is passed in the paramater, then we return that entry.
-     * After getting all elements that have the address of game_address, we implement the filter_map() method which filters then maps.
-     * filter_map() takes a closure as an argument which has to return Option<T>. If the closure returns Some(element) then we return the
-     * element. If the closure returns None then we just skip and try the closure on the next element in local_chain. inside the closure,
-     * we use the if let to make sure that each element is an Entry::App variant. If not we return None but if it is, then we use the try_from()
-     * method on the entry_data found in the Entry::App and convert it to the Game struct cos at this point we are sure that the element
-     * is an Entry::App variant that holds the Game struct as AppEntryValue. try_from returns Result<Self, Self::Error> so we use unwrap to get
-     * the Self which in this case is Game. Since at this point, we are sure that there is only one match for the game_address provided
-     * in the parameter, we use the next() to return the element. Since next() returns an Option<T>, we use the ok_or() method to turn
-     * Option<T> to Result<T, E> and E here being the ZomeApiError::HashNotFound variant which indicates that the game_address provided in the
-     * parameter did not match any entry in the local_chain. we return ZomeApiError::HashNotFound because ZomeApiResult expects any variant
-     * of the ZomeApiError to be returned as an error value.
-     */
+/// Why a move could not be checked yet: the game or a move before it has not reached this agent.
+/// The move may well be valid, so this is not a reason to give up on it.
+pub const GAME_NOT_LOADED: &str =
+    "The game and its previous moves have not reached this agent yet, try again shortly";
+
+/// Everything needed to validate a move, and nothing from any other game: the game and the moves made
+/// in it so far, oldest first. Its size grows with the length of the game rather than the history of its players.
+///
+/// This is not shipped with the move: the HDK can only package the author's own chain, and a move follows
+/// the opponent's moves too. It is the DHT walk validation has done since the local chain reducers went,
+/// so it is only complete once the moves before the new one have reached whoever validates it.
+pub struct GamePackage {
+    pub game: Game,
+    pub moves: Vec<Move>,
 }
 
-pub fn get_moves_local_chain(
-    local_chain: Vec<Entry>,
-    game_address: &Address,
-) -> ZomeApiResult<Vec<Move>> {
-    Ok(local_chain
-        .iter()
-        .filter_map(|entry| {
-            if let Entry::App(entry_type, entry_data) = entry {
-                if entry_type.to_string() == "move" {
-                    Some(Move::try_from(entry_data.clone()).unwrap())
-                } else {
-                    None
+impl GamePackage {
+    /// Follow previous_move back through the DHT from the given move (or the game itself) until the game is reached.
+    /// Anything missing fails with Internal(GAME_NOT_LOADED), moves that don't lead back to the game with ValidationFailed.
+    pub fn build(game_address: &Address, last_address: &Address) -> ZomeApiResult<GamePackage> {
+        let mut moves = Vec::new();
+        let mut address = last_address.clone();
+        while address != *game_address {
+            let previous = match hdk::get_entry(&address)? {
+                Some(Entry::App(ref entry_type, ref entry_data))
+                    if entry_type.to_string() == "move" =>
+                {
+                    Move::try_from(entry_data.clone()).ok()
                 }
-            } else {
-                None
+                Some(_) => None,
+                None => return Err(ZomeApiError::Internal(GAME_NOT_LOADED.into())),
             }
-        })
-        .filter(|game_move| game_move.game == game_address.to_owned())
-        .rev()
-        .collect())
-    /* This one is similar to get_game_local_chain. It takes the local_chain Entries and the game_address as the parameter and returns
-     * a vector of Move wrapped in ZomeApiResult. We first call iter() again then use filter_map() to filter the entries in local chain
-     * to Entry::App variant then if the entry_type (1st element of App variant) is equal to "move" then we return that entry using try_from
-     * method and wrap it in Some(). else we return None if there is no Entry that has the entry_type of "move" and return None also if there
-     * is no Entry:App variant in the local chain. After getting all entries with "move" as the entry_type, we need to filter them and only
-     * yield "move" entry that has the game_address passed in the parameter. That's what the next filter() is for and we check if the game
-     * field of the "move" entry we retrieve from filter_map equals to the game_address being passed in the parameter. We then use rev() to reverse
-     * the iteration when we use the collect() method in order to collect them and turn them into Vec<Move>. // To verify:: why use rev()??
-     */
+            .ok_or_else(|| ZomeApiError::ValidationFailed("Previous move is not a move".into()))?;
+            if previous.game != *game_address {
+                return Err(ZomeApiError::ValidationFailed(
+                    "Previous move is from a different game".into(),
+                ));
+            }
+            address = previous.previous_move.clone();
+            moves.push(previous);
+        }
+        moves.reverse();
+        let game =
+            get_game(game_address).map_err(|_| ZomeApiError::Internal(GAME_NOT_LOADED.into()))?;
+        Ok(GamePackage { game, moves })
+    }
 }
 
-pub fn get_state_local_chain(
-    local_chain: Vec<Entry>,
-    game_address: &Address,
-) -> ZomeApiResult<GameState> {
-    let moves = get_moves_local_chain(local_chain.clone(), game_address)?;
-    let game = get_game_local_chain(local_chain, game_address)?;
-    let new_state = moves
-        .iter()
-        .fold(GameState::initial(), move |state, new_move| {
-            state.evolve(game.clone(), new_move)
-        });
-    Ok(new_state)
-    /* get_state_local_chain is similar to get_state function. It takes local_chain and game_address as parameters and return the GameState.
-     * we first get all the moves associated with the game_address given as parameter using get_moves_local_chain and store them in moves
-     * variable. Then we get the game struct found at game_address using get_game_local_chain. We then create new_state and call iter()
-     * on moves thne use fold() method. fold() will take an empty GameState sturct and then call evolve method on that empty GameState (sored in
-     * state) to store all the Moves. the move keyword before the closure actually means is to move ownership of all of the captured
-     * variables in the closure rather than borrow. To Verify: Not really sure which captured variables we are moving here rather than borrowing.
+pub fn get_state_from_package(package: &GamePackage) -> GameState {
+    fold_moves(&package.game, &package.moves)
+    /* get_state_from_package works like get_state but the moves come from the package rather than following links.
+     * Since the moves in the package are already only those of this game, in order, there is nothing to filter or
+     * reverse, we fold them into the initial GameState with evolve() just like get_state does.
      */
 }
 
-/*=====  End of Local chain functions  ======*/
+/*=====  End of Validation package functions  ======*/
 
 pub fn definition() -> ValidatingEntryType {
     entry!(
@@ -254,12 +433,83 @@ pub fn definition() -> ValidatingEntryType {
                     if game.player_1 == game.player_2 {
                         return Err("Player 1 and Player 2 must be different agents.".into())
                     }
+                    if let Some(range) = &game.suggestion_range {
+                        if range.min > range.max {
+                            return Err("The suggestion range cannot be empty".into())
+                        }
+                        if range.max - range.min > MAX_RANGE_WIDTH {
+                            return Err(format!("The suggestion range can span at most {} numbers", MAX_RANGE_WIDTH + 1))
+                        }
+                    }
+                    if let Some(previous_address) = game.rematch_of {
+                        let previous: Game = utils::get_as_type(previous_address.clone())?;
+                        if previous.player_1 != game.player_2 || previous.player_2 != game.player_1 {
+                            return Err("A rematch must be between the same players with their roles swapped".into())
+                        }
+                        // whether the previous game is over depends on which of its moves have reached us,
+                        // so that is left to accept_rematch rather than checked here
+                    }
+                    if let Some(match_address) = game.in_match {
+                        let game_match: Match = utils::get_as_type(match_address)?;
+                        if !game_match.has_player(&game.player_1) || !game_match.has_player(&game.player_2) {
+                            return Err("A game in a match must be between the two players of the match".into())
+                        }
+                    }
                     Ok(())
                 },
                 _ => {
                     Err("Cannot modify or delete a game".into())
                 }
             }
-        }
+        },
+        links: [
+            to!(
+                "game",
+                link_type: "rematch",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{link, ..} => {
+                            let rematch: Game = utils::get_as_type(link.link.target().clone())?;
+                            if rematch.rematch_of.as_ref() != Some(link.link.base()) {
+                                return Err("Only a rematch of the game can be linked as its rematch".into())
+                            }
+                            // which other rematch links a validator holds depends on gossip, so a second rematch
+                            // is only turned down by accept_rematch and the series follows the earliest one
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove a rematch link".into())
+                        }
+                    }
+                }
+            ),
+            from!(
+                "%agent_id",
+                link_type: "has_game",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{link, ..} => {
+                            // only the players of a game can have it linked from their agent
+                            let game: Game = utils::get_as_type(link.link.target().clone())?;
+                            let player = link.link.base();
+                            if *player == game.player_1 || *player == game.player_2 {
+                                Ok(())
+                            } else {
+                                Err("Games can only be linked from one of their players".into())
+                            }
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove a game link".into())
+                        }
+                    }
+                }
+            )
+        ]
     )
 }
@@ -0,0 +1,253 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    utils, AGENT_ADDRESS,
+};
+
+use crate::game::{self, Game, ResultTally};
+use crate::messaging::{self, DirectMessage};
+use crate::{SuggestionRange, SwapRules, ROUNDS_PER_GAME};
+
+/**
+ *
+ * A Match is a best-of-N series between two players, e.g. best of 3 has a target of 2 wins.
+ * The entry is updated each time a game is added to it so the latest version always holds
+ * the full ordered list of games.
+ *
+ */
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Match {
+    pub player_1: Address,
+    pub player_2: Address,
+    pub target_wins: usize,
+    pub games: Vec<Address>,
+    pub created_at: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct MatchState {
+    #[serde(rename = "match")]
+    pub game_match: Match,
+    #[serde(flatten)]
+    pub tally: ResultTally,
+    /// The game currently being played, if there is one
+    pub current_game: Option<Address>,
+    pub winner: Option<Address>,
+}
+
+impl Match {
+    pub fn has_player(&self, agent: &Address) -> bool {
+        *agent == self.player_1 || *agent == self.player_2
+    }
+
+    pub fn opponent_of(&self, player: &Address) -> Address {
+        if *player == self.player_1 {
+            self.player_2.clone()
+        } else {
+            self.player_1.clone()
+        }
+    }
+
+    /// The address of the match as it was created, before any games were added. Its games name the match by this
+    /// address, which can be worked out from any later version as every match starts out without games.
+    pub fn first_version(&self) -> Address {
+        let first = Match {
+            games: Vec::new(),
+            ..self.clone()
+        };
+        Entry::App("match".into(), first.into()).address()
+    }
+
+    /// The player that has reached the target number of wins, if any
+    pub fn winner(&self, tally: &ResultTally) -> Option<Address> {
+        if tally.player_1_wins >= self.target_wins {
+            Some(self.player_1.clone())
+        } else if tally.player_2_wins >= self.target_wins {
+            Some(self.player_2.clone())
+        } else {
+            None
+        }
+    }
+}
+
+pub fn handle_create_match(
+    opponent: Address,
+    target_wins: usize,
+    created_at: u32,
+) -> ZomeApiResult<Address> {
+    let new_match = Match {
+        player_1: AGENT_ADDRESS.to_string().into(),
+        player_2: opponent,
+        target_wins,
+        games: Vec::new(),
+        created_at,
+    };
+    hdk::commit_entry(&Entry::App("match".into(), new_match.into()))
+}
+
+const STARTED_BY_OPPONENT: &str =
+    "The opponent has just started the next game of this match, use get_match_state to find it";
+
+/// Start the next game of a match. The players swap roles each game so they take turns going first.
+/// Whether the match is decided and its current game over depends on the moves that have reached us,
+/// so those are only checked here and not when the match is validated.
+pub fn handle_start_match_game(match_address: Address, created_at: u32) -> ZomeApiResult<Address> {
+    let mut game_match: Match = utils::get_as_type(match_address.clone())?;
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    if !game_match.has_player(&me) {
+        return Err(ZomeApiError::Internal(
+            "Only the players of a match can start its games".into(),
+        ));
+    }
+    let tally = game::tally_results(&game_match.player_1, &game_match.games)?;
+    if game_match.winner(&tally).is_some() {
+        return Err(ZomeApiError::Internal("This match has been decided".into()));
+    }
+    if tally.unfinished > 0 {
+        return Err(ZomeApiError::Internal(
+            "The current game of this match is not over yet".into(),
+        ));
+    }
+
+    let (player_1, player_2) = if game_match.games.len() % 2 == 0 {
+        (game_match.player_1.clone(), game_match.player_2.clone())
+    } else {
+        (game_match.player_2.clone(), game_match.player_1.clone())
+    };
+    let new_game = Game {
+        player_1,
+        player_2,
+        created_at,
+        private: false,
+        rematch_of: None,
+        in_match: Some(game_match.first_version()),
+        suggestion_range: Some(SuggestionRange::default()),
+        swap_rules: Some(SwapRules::default()),
+        rounds: Some(ROUNDS_PER_GAME),
+    };
+
+    // the opponent may have started the next game while we were adding up the results
+    let latest: Match = utils::get_as_type(match_address.clone())?;
+    if latest.games != game_match.games {
+        return Err(ZomeApiError::Internal(STARTED_BY_OPPONENT.into()));
+    }
+    let game_address = game::commit_game(new_game)?;
+
+    // get_as_type follows updates, so update the latest version rather than the original
+    let latest_address = Entry::App("match".into(), game_match.clone().into()).address();
+    game_match.games.push(game_address.clone());
+    hdk::update_entry(
+        Entry::App("match".into(), game_match.clone().into()),
+        &latest_address,
+    )?;
+    // if they did so at the same time only one of the games ends up in the latest version of the match,
+    // ours can't be deleted but it is taken off the live games so nobody is pointed at it
+    let latest: Match = utils::get_as_type(match_address)?;
+    if latest.games.last() != Some(&game_address) {
+        game::end_live_game(&game_address)?;
+        return Err(ZomeApiError::Internal(STARTED_BY_OPPONENT.into()));
+    }
+
+    messaging::notify(
+        &game_match.opponent_of(&me),
+        DirectMessage::GameInvitation {
+            game: game_address.clone(),
+            from: me,
+        },
+    )?;
+    Ok(game_address)
+}
+
+pub fn handle_get_match_state(match_address: Address) -> ZomeApiResult<MatchState> {
+    // get_as_type follows updates so this is always the latest version of the match
+    let game_match: Match = utils::get_as_type(match_address)?;
+    let tally = game::tally_results(&game_match.player_1, &game_match.games)?;
+    let winner = game_match.winner(&tally);
+    let current_game = if tally.unfinished > 0 {
+        game_match.games.last().cloned()
+    } else {
+        None
+    };
+    Ok(MatchState {
+        game_match,
+        tally,
+        current_game,
+        winner,
+    })
+}
+
+/// Every game in a match must be between its two players, in either role, and made for this match alone
+fn validate_match_games(game_match: &Match, games: &[Address]) -> Result<(), String> {
+    for game_address in games {
+        let game: Game = utils::get_as_type(game_address.clone())?;
+        if !game_match.has_player(&game.player_1) || !game_match.has_player(&game.player_2) {
+            return Err("All games in a match must be between the two players of the match".into());
+        }
+        if game.in_match != Some(game_match.first_version()) {
+            return Err("A game can only be added to the match it was started for".into());
+        }
+    }
+    Ok(())
+}
+
+pub fn match_def() -> ValidatingEntryType {
+    entry!(
+        name: "match",
+        description: "A best-of-N series of games between two agents",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<Match>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let game_match = Match::from(entry);
+                    if game_match.player_1 == game_match.player_2 {
+                        return Err("Player 1 and Player 2 must be different agents.".into());
+                    }
+                    if !validation_data.sources().iter().any(|source| game_match.has_player(source)) {
+                        return Err("Only one of its players can create a match".into());
+                    }
+                    if game_match.target_wins == 0 {
+                        return Err("A match must need at least one win".into());
+                    }
+                    if !game_match.games.is_empty() {
+                        return Err("A match starts out without games".into());
+                    }
+                    Ok(())
+                },
+                EntryValidationData::Modify{ new_entry, old_entry, validation_data, .. } => {
+                    let new_match = Match::from(new_entry);
+                    let old_match = Match::from(old_entry);
+                    if !validation_data.sources().iter().any(|source| old_match.has_player(source)) {
+                        return Err("Only the players of a match can add games to it".into());
+                    }
+                    if new_match.player_1 != old_match.player_1
+                        || new_match.player_2 != old_match.player_2
+                        || new_match.target_wins != old_match.target_wins
+                        || new_match.created_at != old_match.created_at
+                    {
+                        return Err("Only the games of a match can change".into());
+                    }
+                    if new_match.games.len() != old_match.games.len() + 1
+                        || !new_match.games.starts_with(&old_match.games)
+                    {
+                        return Err("Games can only be added to the end of a match one at a time".into());
+                    }
+                    // whether the match is decided or its last game still going depends on which moves have reached
+                    // the validator, so start_match_game checks that and the new games are only checked against the match
+                    validate_match_games(&new_match, &new_match.games[old_match.games.len()..])
+                },
+                EntryValidationData::Delete{..} => {
+                    Err("Cannot delete a match".into())
+                }
+            }
+        }
+    )
+}
@@ -1,14 +1,19 @@
 use hdk::{
     entry_definition::ValidatingEntryType,
+    error::{ZomeApiError, ZomeApiResult},
     holochain_core_types::{
         dna::entry_types::Sharing, entry::Entry, validation::EntryValidationData,
     },
     holochain_json_api::{error::JsonError, json::JsonString},
-    holochain_persistence_api::cas::content::Address,
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    holochain_wasm_utils::api_serialization::query::{QueryArgsOptions, QueryResult},
+    AGENT_ADDRESS,
 };
+use std::convert::TryFrom;
 
-use crate::game::{get_game_local_chain, get_state_local_chain};
-use crate::MoveType;
+use crate::game::{self, get_state_from_package, Game, GamePackage};
+use crate::private_move::{PrivateMove, SealedMove};
+use crate::{GameState, MoveType};
 
 #[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
 pub struct MoveInput {
@@ -17,13 +22,179 @@ pub struct MoveInput {
     pub timestamp: u32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
-pub struct Move {
-    pub game: Address,
-    pub author: Address,
-    pub move_type: MoveType,
-    pub previous_move: Address,
-    pub timestamp: u32,
+pub use rules::Move;
+
+/// The outcome of checking a move against the rules without making it
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct MoveCheck {
+    pub valid: bool,
+    /// Why the move is not allowed, None if it is valid
+    pub reason: Option<String>,
+    pub next_to_move: Option<Address>,
+    /// What could be played instead, if it is our turn
+    pub legal_moves: Vec<MoveType>,
+}
+
+/// Check a move by this agent against the current state of the game, without committing anything
+pub fn check_move(game: &Game, state: &GameState, input: &MoveInput) -> MoveCheck {
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    let candidate = Move {
+        game: input.game.clone(),
+        author: me.clone(),
+        move_type: input.move_type.clone(),
+        // the rules do not depend on which entry the move follows
+        previous_move: input.game.clone(),
+        timestamp: input.timestamp,
+    };
+    let reason = candidate.is_valid(game.clone(), state.clone()).err();
+    let next_to_move = state.next_to_move(game);
+    let legal_moves = if next_to_move == Some(me) {
+        state.legal_moves(&input.game, game)
+    } else {
+        Vec::new()
+    };
+    MoveCheck {
+        valid: reason.is_none(),
+        reason,
+        next_to_move,
+        legal_moves,
+    }
+}
+
+pub fn handle_validate_move(new_move: MoveInput) -> ZomeApiResult<MoveCheck> {
+    let game = game::get_game(&new_move.game)?;
+    let state = game::get_state(&new_move.game)?;
+    Ok(check_move(&game, &state, &new_move))
+}
+
+/// Commit a move to a public game, linked from the move (or game) it follows.
+/// The rules are checked first so an invalid move fails before anything is committed.
+pub fn commit_move(game: &Game, new_move: MoveInput) -> ZomeApiResult<(Address, Move)> {
+    let state = game::get_state(&new_move.game)?;
+
+    // the new move follows the latest move, or the game itself if no moves have been made.
+    // Validation looks these up in the DHT so they don't need to be on our chain
+    let base_address = match state.moves.last() {
+        Some(last_move) => {
+            published_address(game, last_move, None).map_err(ZomeApiError::Internal)?
+        }
+        None => new_move.game.clone(),
+    };
+
+    let new_move = Move {
+        game: new_move.game,
+        author: AGENT_ADDRESS.to_string().into(),
+        move_type: new_move.move_type,
+        previous_move: base_address.clone(),
+        timestamp: new_move.timestamp,
+    };
+    new_move
+        .is_valid(game.clone(), state)
+        .map_err(ZomeApiError::ValidationFailed)?;
+    // validation walks back from the move we follow, so make sure we can too: a move that only has to
+    // wait for gossip fails here with GAME_NOT_LOADED rather than being rejected as invalid by the commit
+    GamePackage::build(&new_move.game, &base_address)?;
+    let move_entry = Entry::App("move".into(), new_move.clone().into());
+    let move_address = hdk::commit_entry(&move_entry)?;
+    hdk::link_entries(&base_address, &move_address, "", "")?;
+    Ok((move_address, new_move))
+}
+
+/// The address a move can be found at in the DHT. Moves of private games are only published sealed,
+/// so their address can only be worked out with the nonce they were sealed with.
+pub fn published_address(
+    game: &Game,
+    game_move: &Move,
+    nonce: Option<String>,
+) -> Result<Address, String> {
+    match (game.private, nonce) {
+        (false, _) => Ok(Entry::App("move".into(), game_move.clone().into()).address()),
+        (true, Some(nonce)) => Ok(SealedMove::seal(&PrivateMove {
+            game_move: game_move.clone(),
+            nonce,
+        })
+        .address()),
+        (true, None) => Err("A move of a private game can only be checked with its nonce".into()),
+    }
+}
+
+/// A move the opponent sent us directly, kept on our own chain so we can use it before it reaches us via gossip
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub struct CachedMove {
+    pub address: Address,
+    pub game_move: Move,
+}
+
+/// Check a move delivered by another agent and cache it if it is valid for the game as we currently see it.
+/// Moves of private games come with their nonce so we can check they are the move that was sealed.
+pub fn cache_move(
+    from: &Address,
+    address: Address,
+    game_move: Move,
+    nonce: Option<String>,
+) -> Result<(), String> {
+    if game_move.author != *from {
+        return Err("Move was not authored by the agent that sent it".into());
+    }
+    let game = game::get_game(&game_move.game)?;
+    if published_address(&game, &game_move, nonce)? != address {
+        return Err("Move does not match the given address".into());
+    }
+    let state = game::get_state(&game_move.game)?;
+    if game_move.previous_move != game::get_last_move_address(&game_move.game)? {
+        return Err("Move does not follow the latest move we know of".into());
+    }
+    game_move.is_valid(game, state)?;
+
+    let cached_entry = Entry::App(
+        "cached_move".into(),
+        CachedMove { address, game_move }.into(),
+    );
+    hdk::commit_entry(&cached_entry)?;
+    Ok(())
+}
+
+/// All the moves for a game that have been delivered directly to this agent
+pub fn get_cached_moves(game_address: &Address) -> ZomeApiResult<Vec<CachedMove>> {
+    let options = QueryArgsOptions {
+        entries: true,
+        ..Default::default()
+    };
+    match hdk::query_result("cached_move".into(), options)? {
+        QueryResult::Entries(entries) => Ok(entries
+            .into_iter()
+            .filter_map(|(_, entry)| match entry {
+                Entry::App(_, entry_data) => CachedMove::try_from(entry_data).ok(),
+                _ => None,
+            })
+            .filter(|cached| cached.game_move.game == *game_address)
+            .collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+pub fn cached_move_definition() -> ValidatingEntryType {
+    entry!(
+        name: "cached_move",
+        description: "A move received directly from the opponent before it could be retrieved from the DHT",
+        // Private as the move itself is published to the DHT by its author
+        sharing: Sharing::Private,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: | validation_data: hdk::EntryValidationData<CachedMove>| {
+            match validation_data {
+                // the move was checked against the game rules before it was cached
+                EntryValidationData::Create{..} => {
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a cached move".into())
+                }
+            }
+        }
+    )
 }
 
 pub fn definition() -> ValidatingEntryType {
@@ -32,48 +203,38 @@ pub fn definition() -> ValidatingEntryType {
         description: "A move by an agent in a game",
         sharing: Sharing::Public,
         validation_package: || {
-            hdk::ValidationPackageDefinition::ChainFull
+            // everything the validation needs is looked up in the DHT
+            hdk::ValidationPackageDefinition::Entry
         },
 
         validation: | validation_data: hdk::EntryValidationData<Move>| {
         /* In this entry's validation, we match the validation_data with the variants of EntryValidationData.
          * If the variant is Create, meaning if we are trying to create an instance of this entry,
-         * then we run a validation in which we retrieve the source_chain_entries found in the validation_data
-         * of the Create struct variant. we use ok_or to convert the Option<T> to Result<T, Err> then return an error if
-         * we cant retrieve the source chain entry using ? operator.
+         * then we run a validation in which we rebuild the game so far from the DHT. The ? operator
+         * returns an error if any of it can't be retrieved.
          */
             match validation_data {
-                EntryValidationData::Create{entry, validation_data} => {
-                    let mut local_chain = validation_data.package.source_chain_entries
-                        .ok_or("Could not retrieve source chain")?;
-                    hdk::debug(format!("{:?}", local_chain))?;
-
-                    // now load the game and game state from here
+                EntryValidationData::Create{entry, ..} => {
                     /* We use from to convert the entry to Move struct and store it in _new_move. */
                     let _new_move = Move::from(entry);
 
-                    // Sometimes the validating entry is already in the chain when validation runs,
-                    // To make our state reduction work correctly this must be removed
-                    /*
-                     * remove_item is a provided method for Vec<T> which is the
-                     * source_chain_entries' type. so we use remove_item() with argument being a reference
-                     * to Entry::App with "move" AppEntryType and _new_move as the AppEntryValue.
-                     */
-                    local_chain.remove_item(&Entry::App("move".into(), _new_move.clone().into()));
-
+                    // now load the game and the moves before this one from the DHT
                     /*
-                     * In order to get the state, we use the get_state_local_chain and call map_err
-                     * method on it to change all Err to the string literal provided in the expression
-                     * of map_err. Same with the get_game_local_chain.
+                     * Following previous_move back to the game gives us exactly the moves of this game
+                     * that came before the new move, whoever authored them, and nothing else.
                      */
-                    let state = get_state_local_chain(local_chain.clone(), &_new_move.game)?;
-                    let game = get_game_local_chain(local_chain, &_new_move.game)
-                        .map_err(|_| "Could not load game during validation")?;
+                    let package = GamePackage::build(&_new_move.game, &_new_move.previous_move)
+                        .map_err(|error| match error {
+                            ZomeApiError::ValidationFailed(reason) => reason,
+                            // not an invalid move, it just can't be checked until gossip catches up
+                            _ => game::GAME_NOT_LOADED.to_string(),
+                        })?;
+                    let state = get_state_from_package(&package);
 
                     /* Finally, we call is_valid() on _new_move to make sure it is the player's turn,
                      * and make sure that the player is making the right move
                      */
-                    _new_move.is_valid(game, state)
+                    _new_move.is_valid(package.game, state)
                 },
                 _ => {
                     Err("Cannot modify or delete a move".into())
@@ -0,0 +1,137 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_core_types::{entry::Entry, link::LinkMatch},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+};
+
+use crate::game::{self, Game};
+use crate::{GameResult, GameState};
+use rules::GAME_TYPE;
+
+/**
+ *
+ * Every public game is linked from the leaderboard anchor of its game type when it is created.
+ * Standings are never stored, they are added up from the games and their moves on the DHT each time
+ * they are asked for so they can only change by actually playing.
+ *
+ */
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Standing {
+    pub agent: Address,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    pub successful_predictions: usize,
+    pub prediction_attempts: usize,
+    /// successful_predictions / prediction_attempts, 0 before the first prediction
+    pub prediction_rate: f32,
+}
+
+impl Standing {
+    fn new(agent: Address) -> Self {
+        Standing {
+            agent,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            successful_predictions: 0,
+            prediction_attempts: 0,
+            prediction_rate: 0.0,
+        }
+    }
+}
+
+fn anchor_entry(game_type: &str) -> Entry {
+    Entry::App("anchor".into(), format!("{}_leaderboard", game_type).into())
+}
+
+/// Put a newly created game on the leaderboard. Private games are left off as only their players can see the moves.
+pub fn add_game(game: &Game, game_address: &Address) -> ZomeApiResult<()> {
+    if game.private {
+        return Ok(());
+    }
+    let anchor_address = hdk::commit_entry(&anchor_entry(GAME_TYPE))?;
+    hdk::link_entries(&anchor_address, game_address, "ranked_game", "")?;
+    Ok(())
+}
+
+fn load_game(game_address: &Address) -> ZomeApiResult<(Game, GameState)> {
+    Ok((
+        game::get_game(game_address)?,
+        game::get_state(game_address)?,
+    ))
+}
+
+/// The best `limit` agents for a game type, ranked by wins then draws then fewest losses
+pub fn handle_get_leaderboard(game_type: String, limit: usize) -> ZomeApiResult<Vec<Standing>> {
+    let anchor_address = anchor_entry(&game_type).address();
+    let mut standings: Vec<Standing> = Vec::new();
+    for game_address in hdk::get_links(
+        &anchor_address,
+        LinkMatch::Exactly("ranked_game"),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    {
+        // a game that can't be loaded (yet) is left out rather than failing the whole leaderboard
+        let (game, state) = match load_game(&game_address) {
+            Ok(loaded) => loaded,
+            Err(_) => continue,
+        };
+        let result = match state.result(&game) {
+            Some(result) => result,
+            None => continue,
+        };
+        let players = [
+            (
+                &game.player_1,
+                state.player_1_successful_prediction,
+                state.player_1_prediction_attempts,
+            ),
+            (
+                &game.player_2,
+                state.player_2_successful_prediction,
+                state.player_2_prediction_attempts,
+            ),
+        ];
+        for (player, successful_predictions, prediction_attempts) in players.iter() {
+            let index = match standings
+                .iter()
+                .position(|standing| standing.agent == **player)
+            {
+                Some(index) => index,
+                None => {
+                    standings.push(Standing::new((*player).clone()));
+                    standings.len() - 1
+                }
+            };
+            let standing = &mut standings[index];
+            match &result {
+                GameResult::Winner(winner) if winner == *player => standing.wins += 1,
+                GameResult::Winner(_) => standing.losses += 1,
+                GameResult::Draw => standing.draws += 1,
+            }
+            standing.successful_predictions += successful_predictions;
+            standing.prediction_attempts += prediction_attempts;
+        }
+    }
+
+    for standing in standings.iter_mut() {
+        if standing.prediction_attempts > 0 {
+            standing.prediction_rate =
+                standing.successful_predictions as f32 / standing.prediction_attempts as f32;
+        }
+    }
+    // the address is the final tie break so everyone sees the same order
+    standings.sort_by(|a, b| {
+        b.wins
+            .cmp(&a.wins)
+            .then(b.draws.cmp(&a.draws))
+            .then(a.losses.cmp(&b.losses))
+            .then(a.agent.to_string().cmp(&b.agent.to_string()))
+    });
+    standings.truncate(limit);
+    Ok(standings)
+}
@@ -1,4 +1,4 @@
-#![feature(try_from, vec_remove_item, proc_macro_hygiene)]
+#![feature(try_from, proc_macro_hygiene)]
 #[macro_use]
 extern crate hdk;
 extern crate serde;
@@ -10,25 +10,42 @@ extern crate holochain_json_derive;
 
 extern crate hdk_proc_macros;
 use hdk_proc_macros::zome;
+extern crate rules;
 
 use hdk::{
     entry_definition::ValidatingEntryType, error::ZomeApiResult,
-    holochain_core_types::entry::Entry, holochain_persistence_api::cas::content::Address,
-    AGENT_ADDRESS,
+    holochain_persistence_api::cas::content::Address, AGENT_ADDRESS,
 };
 
 // This is where you would import your own game State, MoveType and state_reducer
+use rules::{
+    GameResult, GameState, MoveSchema, MoveType, SuggestionRange, SwapRules, ROUNDS_PER_GAME,
+};
 
-mod your_game;
-use your_game::{GameState, MoveType};
-
+mod chat;
+mod export;
 mod game;
+mod game_match;
 mod game_move;
+mod leaderboard;
 mod matchmaking;
+mod messaging;
+mod private_move;
+mod rematch;
+mod stats;
+mod tournament;
 
+use chat::ChatMessage;
+use export::ExportFormat;
 use game::Game;
-use game_move::{Move, MoveInput};
+use game_match::MatchState;
+use game_move::{MoveCheck, MoveInput};
+use leaderboard::Standing;
 use matchmaking::{GameProposal, GetResponse};
+use messaging::DirectMessage;
+use rematch::{RematchRequest, SeriesScore};
+use stats::PlayerStats;
+use tournament::{Bracket, TournamentFormat};
 
 #[zome]
 pub mod main {
@@ -38,6 +55,11 @@ pub mod main {
         Ok(())
     }
 
+    #[receive]
+    pub fn receive(from: Address, msg_json: String) -> String {
+        messaging::handle_receive(from, msg_json)
+    }
+
     /*=========================================
     =            Entry Definitions            =
     =========================================*/
@@ -52,6 +74,51 @@ pub mod main {
         game_move::definition()
     }
 
+    #[entry_def]
+    fn cached_move_entry_def() -> ValidatingEntryType {
+        game_move::cached_move_definition()
+    }
+
+    #[entry_def]
+    fn private_move_entry_def() -> ValidatingEntryType {
+        private_move::private_move_definition()
+    }
+
+    #[entry_def]
+    fn sealed_move_entry_def() -> ValidatingEntryType {
+        private_move::sealed_move_definition()
+    }
+
+    #[entry_def]
+    fn chat_message_def() -> ValidatingEntryType {
+        chat::chat_message_def()
+    }
+
+    #[entry_def]
+    fn rematch_request_def() -> ValidatingEntryType {
+        rematch::rematch_request_def()
+    }
+
+    #[entry_def]
+    fn match_def() -> ValidatingEntryType {
+        game_match::match_def()
+    }
+
+    #[entry_def]
+    fn tournament_def() -> ValidatingEntryType {
+        tournament::tournament_def()
+    }
+
+    #[entry_def]
+    fn registration_def() -> ValidatingEntryType {
+        tournament::registration_def()
+    }
+
+    #[entry_def]
+    fn tournament_start_def() -> ValidatingEntryType {
+        tournament::tournament_start_def()
+    }
+
     #[entry_def]
     fn game_proposal_def() -> ValidatingEntryType {
         matchmaking::game_proposal_def()
@@ -69,48 +136,78 @@ pub mod main {
     ======================================*/
 
     #[zome_fn("hc_public")]
-    fn create_game(opponent: Address, timestamp: u32) -> ZomeApiResult<Address> {
+    fn create_game(
+        opponent: Address,
+        timestamp: u32,
+        private: Option<bool>,
+        suggestion_range: Option<SuggestionRange>,
+        swap_rules: Option<SwapRules>,
+    ) -> ZomeApiResult<Address> {
         let new_game = Game {
             player_1: AGENT_ADDRESS.to_string().into(),
-            player_2: opponent,
+            player_2: opponent.clone(),
             created_at: timestamp,
+            private: private.unwrap_or(false),
+            rematch_of: None,
+            in_match: None,
+            suggestion_range: Some(suggestion_range.unwrap_or_default()),
+            swap_rules: Some(swap_rules.unwrap_or_default()),
+            rounds: Some(ROUNDS_PER_GAME),
         };
-        let game_entry = Entry::App("game".into(), new_game.into());
-        hdk::commit_entry(&game_entry)
+        let game_address = game::commit_game(new_game)?;
+        messaging::notify(
+            &opponent,
+            DirectMessage::GameInvitation {
+                game: game_address.clone(),
+                from: AGENT_ADDRESS.to_string().into(),
+            },
+        )?;
+        Ok(game_address)
     }
 
     #[zome_fn("hc_public")]
     fn make_move(new_move: MoveInput) -> ZomeApiResult<()> {
-        // get all the moves from the DHT by following the hash chain
-        let moves = game::get_moves(&new_move.game)?;
-
-        // commit the latest move to local chain to allow validation of the next move (if one exists)
-        let base_address = match moves.last() {
-            Some(last_move) => {
-                let new_move_entry = Entry::App("move".into(), last_move.into());
-                hdk::commit_entry(&new_move_entry)?
-            }
-            None => {
-                // no moves have been made so commit the Game
-                let game = game::get_game(&new_move.game)?;
-                let game_entry = Entry::App("game".into(), game.into());
-                hdk::commit_entry(&game_entry)?
-            }
+        let game_address = new_move.game.clone();
+        let game = game::get_game(&game_address)?;
+        let (move_address, new_move, nonce) = if game.private {
+            let (move_address, private_move) = private_move::commit_private_move(&game, new_move)?;
+            (
+                move_address,
+                private_move.game_move,
+                Some(private_move.nonce),
+            )
+        } else {
+            let (move_address, new_move) = game_move::commit_move(&game, new_move)?;
+            (move_address, new_move, None)
         };
+        // the move that finishes the game also takes it off the live games
+        if game::get_state(&game_address)?.result(&game).is_some() {
+            game::end_live_game(&game_address)?;
+        }
 
-        let new_move = Move {
-            game: new_move.game,
-            author: AGENT_ADDRESS.to_string().into(),
-            move_type: new_move.move_type,
-            previous_move: base_address.clone(),
-            timestamp: new_move.timestamp,
-        };
-        let move_entry = Entry::App("move".into(), new_move.into());
-        let move_address = hdk::commit_entry(&move_entry)?;
-        hdk::link_entries(&base_address, &move_address, "", "")?;
+        // push the move straight to the opponent. If they are offline they will find it via the DHT later
+        messaging::notify(
+            &game.opponent_of(&new_move.author),
+            DirectMessage::MoveMade {
+                game: game_address,
+                address: move_address,
+                game_move: new_move,
+                nonce,
+            },
+        )?;
         Ok(())
     }
 
+    #[zome_fn("hc_public")]
+    fn validate_move(new_move: MoveInput) -> ZomeApiResult<MoveCheck> {
+        game_move::handle_validate_move(new_move)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_game(game_address: Address) -> ZomeApiResult<Game> {
+        game::get_game(&game_address)
+    }
+
     #[zome_fn("hc_public")]
     fn get_state(game_address: Address) -> ZomeApiResult<GameState> {
         game::get_state(&game_address)
@@ -123,8 +220,158 @@ pub mod main {
     }
 
     #[zome_fn("hc_public")]
-    fn get_valid_moves() -> ZomeApiResult<Vec<MoveType>> {
-        Ok(MoveType::describe())
+    fn get_state_at(game_address: Address, move_index: usize) -> ZomeApiResult<GameState> {
+        game::get_state_at(&game_address, move_index)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_state_history(game_address: Address) -> ZomeApiResult<Vec<GameState>> {
+        game::get_state_history(&game_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn render_state_at(game_address: Address, move_index: usize) -> ZomeApiResult<String> {
+        let game = game::get_game(&game_address)?;
+        Ok(game::get_state_at(&game_address, move_index)?.render(&game))
+    }
+
+    #[zome_fn("hc_public")]
+    fn next_to_move(game_address: Address) -> ZomeApiResult<Option<Address>> {
+        let game = game::get_game(&game_address)?;
+        Ok(game::get_state(&game_address)?.next_to_move(&game))
+    }
+
+    #[zome_fn("hc_public")]
+    fn games_awaiting_my_move() -> ZomeApiResult<Vec<Address>> {
+        let me: Address = AGENT_ADDRESS.to_string().into();
+        let mut awaiting = Vec::new();
+        for game_address in game::get_agent_games(&me)? {
+            let game = game::get_game(&game_address)?;
+            if game::get_state(&game_address)?.next_to_move(&game) == Some(me.clone()) {
+                awaiting.push(game_address);
+            }
+        }
+        Ok(awaiting)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_valid_moves(game_address: Address) -> ZomeApiResult<Vec<MoveSchema>> {
+        let game = game::get_game(&game_address)?;
+        let state = game::get_state(&game_address)?;
+        Ok(MoveType::describe(&game_address, &game, &state))
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_legal_moves(game_address: Address) -> ZomeApiResult<Vec<MoveType>> {
+        let game = game::get_game(&game_address)?;
+        Ok(game::get_state(&game_address)?.legal_moves(&game_address, &game))
+    }
+
+    #[zome_fn("hc_public")]
+    fn list_live_games() -> ZomeApiResult<Vec<GetResponse<Game>>> {
+        game::get_live_games()
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_leaderboard(game_type: String, limit: usize) -> ZomeApiResult<Vec<Standing>> {
+        leaderboard::handle_get_leaderboard(game_type, limit)
+    }
+
+    #[zome_fn("hc_public")]
+    fn export_game(game_address: Address, format: ExportFormat) -> ZomeApiResult<String> {
+        export::handle_export_game(game_address, format)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_player_stats(agent: Address) -> ZomeApiResult<PlayerStats> {
+        stats::handle_get_player_stats(agent)
+    }
+
+    #[zome_fn("hc_public")]
+    fn send_chat(game_address: Address, message: String, timestamp: u32) -> ZomeApiResult<Address> {
+        chat::handle_send_chat(game_address, message, timestamp)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_chat(game_address: Address, since: u32) -> ZomeApiResult<Vec<ChatMessage>> {
+        chat::handle_get_chat(game_address, since)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_result(game_address: Address) -> ZomeApiResult<Option<GameResult>> {
+        let game = game::get_game(&game_address)?;
+        Ok(game::get_state(&game_address)?.result(&game))
+    }
+
+    #[zome_fn("hc_public")]
+    fn request_rematch(game_address: Address, created_at: u32) -> ZomeApiResult<Address> {
+        rematch::handle_request_rematch(game_address, created_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_rematch_requests(
+        game_address: Address,
+    ) -> ZomeApiResult<Vec<GetResponse<RematchRequest>>> {
+        rematch::handle_get_rematch_requests(game_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn accept_rematch(request_addr: Address, created_at: u32) -> ZomeApiResult<Address> {
+        rematch::handle_accept_rematch(request_addr, created_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_series(game_address: Address) -> ZomeApiResult<SeriesScore> {
+        rematch::handle_get_series(game_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn create_match(
+        opponent: Address,
+        target_wins: usize,
+        created_at: u32,
+    ) -> ZomeApiResult<Address> {
+        game_match::handle_create_match(opponent, target_wins, created_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn start_match_game(match_address: Address, created_at: u32) -> ZomeApiResult<Address> {
+        game_match::handle_start_match_game(match_address, created_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_match_state(match_address: Address) -> ZomeApiResult<MatchState> {
+        game_match::handle_get_match_state(match_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn create_tournament(
+        name: String,
+        format: TournamentFormat,
+        player_cap: usize,
+        created_at: u32,
+    ) -> ZomeApiResult<Address> {
+        tournament::handle_create_tournament(name, format, player_cap, created_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn register(tournament_address: Address) -> ZomeApiResult<()> {
+        tournament::handle_register(tournament_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn start_tournament(tournament_address: Address, started_at: u32) -> ZomeApiResult<Address> {
+        tournament::handle_start_tournament(tournament_address, started_at)
+    }
+
+    #[zome_fn("hc_public")]
+    fn get_bracket(tournament_address: Address) -> ZomeApiResult<Bracket> {
+        tournament::handle_get_bracket(tournament_address)
+    }
+
+    #[zome_fn("hc_public")]
+    fn report_result(tournament_address: Address, game_address: Address) -> ZomeApiResult<()> {
+        tournament::handle_report_result(tournament_address, game_address)
     }
 
     #[zome_fn("hc_public")]
@@ -2,7 +2,10 @@ use hdk::{
     entry_definition::ValidatingEntryType,
     error::ZomeApiResult,
     holochain_core_types::{
-        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
+        dna::entry_types::Sharing,
+        entry::Entry,
+        link::LinkMatch,
+        validation::{EntryValidationData, LinkValidationData},
     },
     holochain_json_api::{
         error::JsonError,
@@ -15,7 +18,9 @@ use hdk::{
 use serde::Serialize;
 use std::fmt::Debug;
 
-use crate::game::Game;
+use crate::game::{self, Game};
+use crate::messaging::{self, DirectMessage};
+use crate::{SuggestionRange, SwapRules, ROUNDS_PER_GAME};
 
 #[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
 pub struct GameProposal {
@@ -92,14 +97,28 @@ pub fn handle_accept_proposal(proposal_addr: Address, created_at: u32) -> ZomeAp
     // create the new game
     let game = Game {
         player_1: AGENT_ADDRESS.to_string().into(),
-        player_2: proposal.agent,
+        player_2: proposal.agent.clone(),
         created_at,
+        private: false,
+        rematch_of: None,
+        in_match: None,
+        suggestion_range: Some(SuggestionRange::default()),
+        swap_rules: Some(SwapRules::default()),
+        rounds: Some(ROUNDS_PER_GAME),
     };
-    let game_entry = Entry::App("game".into(), game.into());
-    let game_addr = hdk::commit_entry(&game_entry)?;
+    let game_addr = game::commit_game(game)?;
 
     // link to the proposal
     hdk::link_entries(&proposal_addr, &game_addr, "from_proposal", "")?;
+
+    // let the proposer know straight away rather than waiting for them to check responses
+    messaging::notify(
+        &proposal.agent,
+        DirectMessage::ProposalAccepted {
+            proposal: proposal_addr,
+            game: game_addr.clone(),
+        },
+    )?;
     Ok(game_addr)
 }
 
@@ -188,6 +207,52 @@ pub fn anchor_def() -> ValidatingEntryType {
                 validation: | _validation_data: hdk::LinkValidationData| {
                     Ok(())
                 }
+            ),
+            to!(
+                "game",
+                link_type: "live_game",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{link, ..} => {
+                            // the anchor only lists games
+                            let _game: Game = hdk::utils::get_as_type(link.link.target().clone())?;
+                            Ok(())
+                        },
+                        // a game is taken off by the player who finished it, whether it really is over is left to make_move
+                        LinkValidationData::LinkRemove{link, validation_data} => {
+                            let game: Game = hdk::utils::get_as_type(link.link.target().clone())?;
+                            if !validation_data.sources().iter().any(|source| *source == game.player_1 || *source == game.player_2) {
+                                return Err("Only the players of a game can take it off the live games".into());
+                            }
+                            Ok(())
+                        }
+                    }
+                }
+            ),
+            to!(
+                "game",
+                link_type: "ranked_game",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{link, ..} => {
+                            // nobody else could check the results of a private game
+                            let game: Game = hdk::utils::get_as_type(link.link.target().clone())?;
+                            if game.private {
+                                return Err("Private games cannot go on the leaderboard".into());
+                            }
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove a game from the leaderboard".into())
+                        }
+                    }
+                }
             )
         ]
     )
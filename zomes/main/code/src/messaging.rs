@@ -0,0 +1,158 @@
+use hdk::{
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::time::Timeout,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use crate::chat::ChatMessage;
+use crate::game;
+use crate::game_move::{self, Move};
+use crate::private_move::{self, PrivateMove};
+
+/// How long to wait for the other agent to answer a direct message we need the answer to
+const SEND_TIMEOUT_MS: usize = 5000;
+
+/// How long to wait for the other agent to acknowledge a notification. The call that sent it is held up
+/// for this long when they are offline, and they will find the change via the DHT anyway, so it is kept short
+const NOTIFY_TIMEOUT_MS: usize = 500;
+
+/**
+ * Messages sent directly from one agent to another (node-to-node) rather than through the DHT.
+ * Receiving one of these re-emits it as a signal so UIs connected to the receiving conductor
+ * can update straight away instead of polling.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+pub enum DirectMessage {
+    GameInvitation {
+        game: Address,
+        from: Address,
+    },
+    ProposalAccepted {
+        proposal: Address,
+        game: Address,
+    },
+    MoveMade {
+        game: Address,
+        address: Address,
+        game_move: Move,
+        /// The nonce a move in a private game was sealed with, so the opponent can check it against the sealed move
+        #[serde(default)]
+        nonce: Option<String>,
+    },
+    ChatReceived {
+        game: Address,
+        chat_message: ChatMessage,
+    },
+    RematchRequested {
+        game: Address,
+        request: Address,
+    },
+    /// Asks the opponent for the moves they made in a private game. Answered with the moves rather than "ok"
+    RequestMoves {
+        game: Address,
+    },
+}
+
+impl DirectMessage {
+    /// The name of the signal emitted when this message is received
+    pub fn signal_name(&self) -> &'static str {
+        match self {
+            DirectMessage::GameInvitation { .. } => "game_invitation",
+            DirectMessage::ProposalAccepted { .. } => "proposal_accepted",
+            DirectMessage::MoveMade { .. } => "move_made",
+            DirectMessage::ChatReceived { .. } => "chat_received",
+            DirectMessage::RematchRequested { .. } => "rematch_requested",
+            DirectMessage::RequestMoves { .. } => "request_moves",
+        }
+    }
+
+    /// The game the message is about
+    pub fn game(&self) -> &Address {
+        match self {
+            DirectMessage::GameInvitation { game, .. }
+            | DirectMessage::ProposalAccepted { game, .. }
+            | DirectMessage::MoveMade { game, .. }
+            | DirectMessage::ChatReceived { game, .. }
+            | DirectMessage::RematchRequested { game, .. }
+            | DirectMessage::RequestMoves { game } => game,
+        }
+    }
+
+    /// Check the message could have come from the given agent: only a player of the game it names
+    /// can send it, and anything it says that agent did has to be theirs
+    fn check_sender(&self, from: &Address) -> Result<(), String> {
+        let game = game::get_game(self.game())?;
+        if *from != game.player_1 && *from != game.player_2 {
+            return Err("Only the players of a game can send messages about it".into());
+        }
+        let claimed = match self {
+            DirectMessage::GameInvitation { from: inviter, .. } => Some(inviter),
+            DirectMessage::MoveMade { game_move, .. } => Some(&game_move.author),
+            DirectMessage::ChatReceived { chat_message, .. } => Some(&chat_message.author),
+            _ => None,
+        };
+        match claimed {
+            Some(claimed) if claimed != from => {
+                Err("A message can only be about what its sender did".into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Send a message to another agent. The other agent being offline is not an error
+/// as they will see the change via the DHT once they come back.
+pub fn notify(to_agent: &Address, message: DirectMessage) -> ZomeApiResult<()> {
+    let payload = JsonString::from(message).to_string();
+    match hdk::send(to_agent.clone(), payload, Timeout::new(NOTIFY_TIMEOUT_MS)) {
+        Ok(ref response) if response == "ok" => {}
+        Ok(response) => hdk::debug(format!("{} rejected message: {}", to_agent, response))?,
+        Err(e) => hdk::debug(format!("Could not notify {}: {:?}", to_agent, e))?,
+    }
+    Ok(())
+}
+
+/// Ask the opponent in a private game for the moves only they hold
+pub fn request_moves(opponent: &Address, game: &Address) -> ZomeApiResult<Vec<PrivateMove>> {
+    let payload = JsonString::from(DirectMessage::RequestMoves { game: game.clone() }).to_string();
+    let response = hdk::send(opponent.clone(), payload, Timeout::new(SEND_TIMEOUT_MS))?;
+    serde_json::from_str(&response).map_err(|_| ZomeApiError::Internal(response))
+}
+
+/// Handle a direct message from another agent by passing it on as a signal
+pub fn handle_receive(from: Address, msg_json: String) -> String {
+    match serde_json::from_str::<DirectMessage>(&msg_json) {
+        Ok(DirectMessage::RequestMoves { game }) => {
+            match private_move::handle_request_moves(&from, &game) {
+                Ok(moves) => {
+                    serde_json::to_string(&moves).unwrap_or_else(|e| format!("error: {}", e))
+                }
+                Err(e) => format!("error: {}", e),
+            }
+        }
+        Ok(message) => {
+            let _ = hdk::debug(format!("Received {} from {}", message.signal_name(), from));
+            if let Err(e) = message.check_sender(&from) {
+                return format!("error: {}", e);
+            }
+            // moves are delivered in full so we can see them before gossip catches up
+            if let DirectMessage::MoveMade {
+                address,
+                game_move,
+                nonce,
+                ..
+            } = message.clone()
+            {
+                if let Err(e) = game_move::cache_move(&from, address, game_move, nonce) {
+                    return format!("error: {}", e);
+                }
+            }
+            match hdk::emit_signal(message.signal_name(), JsonString::from(message.clone())) {
+                Ok(()) => "ok".into(),
+                Err(e) => format!("error: {:?}", e),
+            }
+        }
+        Err(e) => format!("error: could not parse message: {}", e),
+    }
+}
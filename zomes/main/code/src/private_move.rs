@@ -0,0 +1,283 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    holochain_wasm_utils::api_serialization::query::{QueryArgsOptions, QueryResult},
+    utils, AGENT_ADDRESS,
+};
+use std::convert::TryFrom;
+
+use crate::game::{self, Game};
+use crate::game_move::{CachedMove, Move, MoveInput};
+use crate::MoveType;
+
+/**
+ *
+ * In a private game the full moves are committed as private entries and only ever sent directly to the opponent.
+ * What goes on the DHT is a SealedMove which carries everything needed to check turn order but only the hash
+ * of the move itself. Anyone can verify that the players are taking turns, only the players can read the moves.
+ *
+ */
+
+/// The full contents of a move in a private game. The few moves a player could have made are easy to hash one by one,
+/// so each is sealed along with a random nonce that only the opponent is given.
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct PrivateMove {
+    pub game_move: Move,
+    pub nonce: String,
+}
+
+impl PrivateMove {
+    /// The address of the private_move entry, which the sealed move holds in place of the move
+    pub fn address(&self) -> Address {
+        Entry::App("private_move".into(), self.clone().into()).address()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson, PartialEq)]
+pub struct SealedMove {
+    pub game: Address,
+    pub author: Address,
+    pub previous_move: Address,
+    pub timestamp: u32,
+    /// Address of the private_move entry holding the full move and its nonce
+    pub content: Address,
+    /// Swaps are public so validators can tell when a player moves twice in a row after agreeing to one.
+    /// Left out of other moves so they keep the address they had before swaps were published.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub swap: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl SealedMove {
+    pub fn seal(private_move: &PrivateMove) -> Self {
+        let game_move = &private_move.game_move;
+        SealedMove {
+            game: game_move.game.clone(),
+            author: game_move.author.clone(),
+            previous_move: game_move.previous_move.clone(),
+            timestamp: game_move.timestamp,
+            content: private_move.address(),
+            swap: game_move.move_type == MoveType::Swap {},
+        }
+    }
+
+    /// Whether this is a swap agreeing to the one the opponent asked for. Swaps in a row take turns at asking and
+    /// agreeing, the first of them asks.
+    pub fn agrees_to_swap(&self) -> ZomeApiResult<bool> {
+        let mut swaps_in_a_row = 0;
+        let mut current = self.clone();
+        while current.swap {
+            swaps_in_a_row += 1;
+            if current.previous_move == current.game {
+                break;
+            }
+            current = utils::get_as_type(current.previous_move.clone())?;
+        }
+        Ok(swaps_in_a_row > 0 && swaps_in_a_row % 2 == 0)
+    }
+
+    /// The address this sealed move is published at in the DHT
+    pub fn address(&self) -> Address {
+        Entry::App("sealed_move".into(), self.clone().into()).address()
+    }
+}
+
+/// A fresh random nonce. The HDK has no random number generator of its own, so this is the public key of
+/// a one-time key pair, which the conductor generates from fresh randomness and which is not used for anything else.
+fn new_nonce() -> ZomeApiResult<String> {
+    Ok(hdk::sign_one_time(vec![String::new()])?.pub_key)
+}
+
+/// Commit a move to a private game. The rules are checked here as the DHT cannot see the move
+pub fn commit_private_move(
+    game: &Game,
+    new_move: MoveInput,
+) -> ZomeApiResult<(Address, PrivateMove)> {
+    let state = game::get_state(&new_move.game)?;
+    let previous_move = game::get_last_move_address(&new_move.game)?;
+
+    let new_move = Move {
+        game: new_move.game,
+        author: AGENT_ADDRESS.to_string().into(),
+        move_type: new_move.move_type,
+        previous_move: previous_move.clone(),
+        timestamp: new_move.timestamp,
+    };
+    new_move
+        .is_valid(game.clone(), state)
+        .map_err(ZomeApiError::ValidationFailed)?;
+    let private_move = PrivateMove {
+        game_move: new_move,
+        nonce: new_nonce()?,
+    };
+
+    let private_entry = Entry::App("private_move".into(), private_move.clone().into());
+    hdk::commit_entry(&private_entry)?;
+    let sealed_entry = Entry::App("sealed_move".into(), SealedMove::seal(&private_move).into());
+    let sealed_address = hdk::commit_entry(&sealed_entry)?;
+    hdk::link_entries(&previous_move, &sealed_address, "sealed_move", "")?;
+    Ok((sealed_address, private_move))
+}
+
+/// The moves this agent has made in a private game, along with the nonces they were sealed with
+fn get_own_private_moves(game_address: &Address) -> ZomeApiResult<Vec<PrivateMove>> {
+    let options = QueryArgsOptions {
+        entries: true,
+        ..Default::default()
+    };
+    match hdk::query_result("private_move".into(), options)? {
+        QueryResult::Entries(entries) => Ok(entries
+            .into_iter()
+            .filter_map(|(_, entry)| match entry {
+                Entry::App(_, entry_data) => PrivateMove::try_from(entry_data).ok(),
+                _ => None,
+            })
+            .filter(|private_move| private_move.game_move.game == *game_address)
+            .collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// The moves this agent has made in a private game, keyed by the address of their sealed entry
+pub fn get_private_moves(game_address: &Address) -> ZomeApiResult<Vec<CachedMove>> {
+    Ok(get_own_private_moves(game_address)?
+        .into_iter()
+        .map(|private_move| CachedMove {
+            address: SealedMove::seal(&private_move).address(),
+            game_move: private_move.game_move,
+        })
+        .collect())
+}
+
+/// Answer the opponent asking for the moves we made in a private game. Nobody else gets them, or their nonces.
+pub fn handle_request_moves(
+    from: &Address,
+    game_address: &Address,
+) -> Result<Vec<PrivateMove>, String> {
+    let game = game::get_game(game_address)?;
+    if *from != game.player_1 && *from != game.player_2 {
+        return Err("Only the players of a private game can see its moves".into());
+    }
+    Ok(get_own_private_moves(game_address)?)
+}
+
+pub fn private_move_definition() -> ValidatingEntryType {
+    entry!(
+        name: "private_move",
+        description: "The full contents of a move in a private game",
+        // Private so it only lives on the author's chain. The opponent is sent it directly
+        sharing: Sharing::Private,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: | validation_data: hdk::EntryValidationData<PrivateMove>| {
+            match validation_data {
+                // only the author ever validates this entry. The game rules are checked before it is committed,
+                // see commit_private_move, and again by the opponent when they are sent the move
+                EntryValidationData::Create{entry, validation_data} => {
+                    let private_move = PrivateMove::from(entry);
+                    let game_move = private_move.game_move;
+                    if !validation_data.sources().contains(&game_move.author) {
+                        return Err("Cannot author a move for another agent".into());
+                    }
+                    if private_move.nonce.is_empty() {
+                        return Err("A private move must be sealed with a nonce".into());
+                    }
+                    let game: Game = utils::get_as_type(game_move.game.clone())?;
+                    if !game.private {
+                        return Err("Only private games can have private moves".into());
+                    }
+                    if game_move.author != game.player_1 && game_move.author != game.player_2 {
+                        return Err("Only the players of a game can make moves".into());
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a move".into())
+                }
+            }
+        }
+    )
+}
+
+pub fn sealed_move_definition() -> ValidatingEntryType {
+    entry!(
+        name: "sealed_move",
+        description: "The public record of a move in a private game, without its contents",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: | validation_data: hdk::EntryValidationData<SealedMove>| {
+            match validation_data {
+                EntryValidationData::Create{entry, validation_data} => {
+                    let sealed_move = SealedMove::from(entry);
+                    if !validation_data.sources().contains(&sealed_move.author) {
+                        return Err("Cannot author a move for another agent".into());
+                    }
+                    let game: Game = utils::get_as_type(sealed_move.game.clone())?;
+                    if !game.private {
+                        return Err("Only private games can have sealed moves".into());
+                    }
+                    if sealed_move.author != game.player_1 && sealed_move.author != game.player_2 {
+                        return Err("Only the players of a game can make moves".into());
+                    }
+                    // turn order is all that can be checked without seeing the moves
+                    if sealed_move.previous_move == sealed_move.game {
+                        if sealed_move.author != game.player_2 {
+                            return Err("Player 2 must start the game".into());
+                        }
+                    } else {
+                        let previous: SealedMove = utils::get_as_type(sealed_move.previous_move.clone())?;
+                        if previous.game != sealed_move.game {
+                            return Err("Previous move is from a different game".into());
+                        }
+                        // players take turns, except that whoever agrees to a swap goes on to suggest.
+                        // A sealed move lying about being a swap is caught by the opponent when they get the move
+                        let require_consent = game.swap_rules.map_or(false, |rules| rules.require_consent);
+                        let accepted_swap = require_consent && previous.agrees_to_swap()?;
+                        if (previous.author == sealed_move.author) != accepted_swap {
+                            return Err("It is not this player turn".into());
+                        }
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a move".into())
+                }
+            }
+        },
+        links: [
+            from!(
+                "game",
+                link_type: "sealed_move",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | _validation_data: hdk::LinkValidationData| {
+                    Ok(())
+                }
+            ),
+            from!(
+                "sealed_move",
+                link_type: "sealed_move",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | _validation_data: hdk::LinkValidationData| {
+                    Ok(())
+                }
+            )
+        ]
+    )
+}
@@ -0,0 +1,203 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{
+        dna::entry_types::Sharing, entry::Entry, link::LinkMatch, validation::EntryValidationData,
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    utils, AGENT_ADDRESS,
+};
+
+use crate::game::{self, Game, ResultTally};
+use crate::matchmaking::GetResponse;
+use crate::messaging::{self, DirectMessage};
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct RematchRequest {
+    pub game: Address,
+    pub requester: Address,
+    pub created_at: u32,
+}
+
+/// The combined score of a game and all the rematches that followed it.
+/// player_1 and player_2 are the players of the first game in the series.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct SeriesScore {
+    pub games: Vec<Address>,
+    pub player_1: Address,
+    pub player_2: Address,
+    #[serde(flatten)]
+    pub tally: ResultTally,
+}
+
+pub fn handle_request_rematch(game_address: Address, created_at: u32) -> ZomeApiResult<Address> {
+    let game = game::get_game(&game_address)?;
+    if game::get_state(&game_address)?.result(&game).is_none() {
+        return Err(ZomeApiError::Internal(
+            "Can only ask for a rematch once the game is over".into(),
+        ));
+    }
+    let request = RematchRequest {
+        game: game_address.clone(),
+        requester: AGENT_ADDRESS.to_string().into(),
+        created_at,
+    };
+    let entry = Entry::App("rematch_request".into(), request.clone().into());
+    let request_address = hdk::commit_entry(&entry)?;
+    hdk::link_entries(&game_address, &request_address, "rematch_request", "")?;
+
+    messaging::notify(
+        &game.opponent_of(&request.requester),
+        DirectMessage::RematchRequested {
+            game: game_address,
+            request: request_address.clone(),
+        },
+    )?;
+    Ok(request_address)
+}
+
+pub fn handle_get_rematch_requests(
+    game_address: Address,
+) -> ZomeApiResult<Vec<GetResponse<RematchRequest>>> {
+    Ok(utils::get_links_and_load_type(
+        &game_address,
+        LinkMatch::Exactly("rematch_request"),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .map(|request: RematchRequest| {
+        let address = Entry::App("rematch_request".into(), request.clone().into()).address();
+        GetResponse {
+            entry: request,
+            address,
+        }
+    })
+    .collect())
+}
+
+/// Accept the opponent's request by starting the new game. Player 2 always goes first so swapping
+/// the players means the other player suggests first this time.
+/// That the game is over and has not been rematched yet is only checked here, against what this agent can see:
+/// validation can't rely on either, so should two rematches get through anyway the series follows the earliest.
+pub fn handle_accept_rematch(request_addr: Address, created_at: u32) -> ZomeApiResult<Address> {
+    let request: RematchRequest = utils::get_as_type(request_addr)?;
+    let previous_game = game::get_game(&request.game)?;
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    if request.requester == me || previous_game.opponent_of(&request.requester) != me {
+        return Err(ZomeApiError::Internal(
+            "Only the opponent of the requester can accept a rematch".into(),
+        ));
+    }
+    if game::get_state(&request.game)?
+        .result(&previous_game)
+        .is_none()
+    {
+        return Err(ZomeApiError::Internal(
+            "Only a finished game can be rematched".into(),
+        ));
+    }
+    if get_rematch(&request.game)?.is_some() {
+        return Err(ZomeApiError::Internal(
+            "This game has already been rematched".into(),
+        ));
+    }
+
+    let new_game = Game {
+        player_1: previous_game.player_2.clone(),
+        player_2: previous_game.player_1.clone(),
+        created_at,
+        private: previous_game.private,
+        rematch_of: Some(request.game.clone()),
+        in_match: None,
+        suggestion_range: previous_game.suggestion_range.clone(),
+        swap_rules: previous_game.swap_rules.clone(),
+        rounds: previous_game.rounds,
+    };
+    let game_address = game::commit_game(new_game)?;
+    hdk::link_entries(&request.game, &game_address, "rematch", "")?;
+
+    messaging::notify(
+        &request.requester,
+        DirectMessage::GameInvitation {
+            game: game_address.clone(),
+            from: me,
+        },
+    )?;
+    Ok(game_address)
+}
+
+/// The rematch of a game, the earliest created if more than one was linked
+fn get_rematch(game_address: &Address) -> ZomeApiResult<Option<Address>> {
+    let mut rematches = Vec::new();
+    for address in
+        hdk::get_links(game_address, LinkMatch::Exactly("rematch"), LinkMatch::Any)?.addresses()
+    {
+        rematches.push((game::get_game(&address)?.created_at, address));
+    }
+    Ok(rematches.into_iter().min().map(|(_, address)| address))
+}
+
+/// Follow the rematch references back to the first game and the rematch links forward to the latest,
+/// adding up the results of all the finished games on the way
+pub fn handle_get_series(game_address: Address) -> ZomeApiResult<SeriesScore> {
+    let mut first_address = game_address;
+    let mut first_game = game::get_game(&first_address)?;
+    while let Some(previous_address) = first_game.rematch_of.clone() {
+        first_game = game::get_game(&previous_address)?;
+        first_address = previous_address;
+    }
+
+    let mut games = vec![first_address];
+    while let Some(next_address) = get_rematch(games.last().unwrap())? {
+        games.push(next_address);
+    }
+
+    Ok(SeriesScore {
+        tally: game::tally_results(&first_game.player_1, &games)?,
+        games,
+        player_1: first_game.player_1,
+        player_2: first_game.player_2,
+    })
+}
+
+pub fn rematch_request_def() -> ValidatingEntryType {
+    entry!(
+        name: "rematch_request",
+        description: "A player asking to play the opponent of a finished game again",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<RematchRequest>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let request = RematchRequest::from(entry);
+                    if !validation_data.sources().contains(&request.requester) {
+                        return Err("Cannot request a rematch for another agent".into());
+                    }
+                    let game: Game = utils::get_as_type(request.game.clone())?;
+                    if request.requester != game.player_1 && request.requester != game.player_2 {
+                        return Err("Only the players of a game can ask for a rematch".into());
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a rematch request".into())
+                }
+            }
+        },
+        links: [
+            from!(
+                "game",
+                link_type: "rematch_request",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | _validation_data: hdk::LinkValidationData| {
+                    Ok(())
+                }
+            )
+        ]
+    )
+}
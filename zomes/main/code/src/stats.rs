@@ -0,0 +1,143 @@
+use hdk::{
+    error::ZomeApiResult,
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+use crate::game;
+use crate::MoveType;
+
+/// How well an agent predicted in a single game
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameAccuracy {
+    pub game: Address,
+    pub created_at: u32,
+    pub successful_predictions: usize,
+    pub prediction_attempts: usize,
+    pub accuracy: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SuggestionCount {
+    pub suggestion: usize,
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct PlayerStats {
+    pub agent: Address,
+    pub games_played: usize,
+    pub successful_suggestions: usize,
+    pub suggestion_attempts: usize,
+    pub successful_predictions: usize,
+    pub prediction_attempts: usize,
+    /// Across all games, 0 before the first prediction
+    pub prediction_accuracy: f32,
+    /// One entry per game the agent has predicted in, oldest game first
+    pub accuracy_over_time: Vec<GameAccuracy>,
+    /// Every number the agent has suggested, most often suggested first
+    pub favourite_suggestions: Vec<SuggestionCount>,
+}
+
+fn ratio(successes: usize, attempts: usize) -> f32 {
+    if attempts == 0 {
+        0.0
+    } else {
+        successes as f32 / attempts as f32
+    }
+}
+
+/// Add up the per game counters of GameState across all of an agent's games.
+/// Private games are skipped unless we are one of their players, as nobody else can see their moves.
+pub fn handle_get_player_stats(agent: Address) -> ZomeApiResult<PlayerStats> {
+    let mut stats = PlayerStats {
+        agent: agent.clone(),
+        games_played: 0,
+        successful_suggestions: 0,
+        suggestion_attempts: 0,
+        successful_predictions: 0,
+        prediction_attempts: 0,
+        prediction_accuracy: 0.0,
+        accuracy_over_time: Vec::new(),
+        favourite_suggestions: Vec::new(),
+    };
+
+    for game_address in game::get_agent_games(&agent)? {
+        let game = game::get_game(&game_address)?;
+        let state = match game::get_state(&game_address) {
+            Ok(state) => state,
+            Err(_) if game.private => continue,
+            Err(e) => return Err(e),
+        };
+        let (
+            successful_suggestions,
+            suggestion_attempts,
+            successful_predictions,
+            prediction_attempts,
+        ) = if game.player_1 == agent {
+            (
+                state.player_1_successful_suggestion,
+                state.player_1_suggestion_attempts,
+                state.player_1_successful_prediction,
+                state.player_1_prediction_attempts,
+            )
+        } else {
+            (
+                state.player_2_successful_suggestion,
+                state.player_2_suggestion_attempts,
+                state.player_2_successful_prediction,
+                state.player_2_prediction_attempts,
+            )
+        };
+        // a game only counts as played once the agent has made a move in it
+        if state
+            .moves
+            .iter()
+            .any(|game_move| game_move.author == agent)
+        {
+            stats.games_played += 1;
+        }
+        stats.successful_suggestions += successful_suggestions;
+        stats.suggestion_attempts += suggestion_attempts;
+        stats.successful_predictions += successful_predictions;
+        stats.prediction_attempts += prediction_attempts;
+        if prediction_attempts > 0 {
+            stats.accuracy_over_time.push(GameAccuracy {
+                game: game_address.clone(),
+                created_at: game.created_at,
+                successful_predictions,
+                prediction_attempts,
+                accuracy: ratio(successful_predictions, prediction_attempts),
+            });
+        }
+
+        for game_move in state
+            .moves
+            .iter()
+            .filter(|game_move| game_move.author == agent)
+        {
+            if let MoveType::Suggest { suggestion } = game_move.move_type {
+                match stats
+                    .favourite_suggestions
+                    .iter_mut()
+                    .find(|counted| counted.suggestion == suggestion)
+                {
+                    Some(counted) => counted.count += 1,
+                    None => stats.favourite_suggestions.push(SuggestionCount {
+                        suggestion,
+                        count: 1,
+                    }),
+                }
+            }
+        }
+    }
+
+    stats.prediction_accuracy = ratio(stats.successful_predictions, stats.prediction_attempts);
+    stats
+        .accuracy_over_time
+        .sort_by_key(|game_accuracy| game_accuracy.created_at);
+    stats
+        .favourite_suggestions
+        .sort_by(|a, b| b.count.cmp(&a.count).then(a.suggestion.cmp(&b.suggestion)));
+    Ok(stats)
+}
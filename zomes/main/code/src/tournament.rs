@@ -0,0 +1,683 @@
+use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::{ZomeApiError, ZomeApiResult},
+    holochain_core_types::{
+        dna::entry_types::Sharing,
+        entry::Entry,
+        link::LinkMatch,
+        validation::{EntryValidationData, LinkValidationData},
+    },
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::{Address, AddressableContent},
+    utils, AGENT_ADDRESS,
+};
+
+use crate::game::{self, Game};
+use crate::matchmaking::GetResponse;
+use crate::GameResult;
+
+/**
+ *
+ * Tournaments are built out of ordinary games. Players register for a tournament and once enough have the organiser
+ * starts it, which fixes the players and so the pairings. Once two players have played the game for one of their pairings
+ * either of them reports it. The bracket is never stored, it is recomputed from the players fixed at the start and
+ * the reported games every time so anyone can check it.
+ *
+ */
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TournamentFormat {
+    RoundRobin,
+    SingleElimination,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Tournament {
+    pub organiser: Address,
+    pub name: String,
+    pub format: TournamentFormat,
+    pub player_cap: usize,
+    pub created_at: u32,
+}
+
+/// A player signing up for a tournament. An entry rather than a bare link so the start can check each of its players signed up
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Registration {
+    pub tournament: Address,
+    pub player: Address,
+}
+
+/// The organiser closing registration. The pairings only ever come from these players, so registering after
+/// the start changes nothing, and results are reported against the start rather than the tournament.
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct TournamentStart {
+    pub tournament: Address,
+    /// Sorted by address so the pairings are the same for everyone
+    pub players: Vec<Address>,
+    pub started_at: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pairing {
+    pub player_1: Address,
+    /// None if player 1 has a bye this round
+    pub player_2: Option<Address>,
+    pub game: Option<Address>,
+    pub result: Option<GameResult>,
+}
+
+impl Pairing {
+    fn is_between(&self, game: &Game) -> bool {
+        let player_2 = match &self.player_2 {
+            Some(player_2) => player_2,
+            None => return false,
+        };
+        (game.player_1 == self.player_1 && game.player_2 == *player_2)
+            || (game.player_2 == self.player_1 && game.player_1 == *player_2)
+    }
+
+    /// Byes are resolved straight away. Elimination pairings need a winner, round robin pairings also accept a draw.
+    fn is_resolved(&self, format: &TournamentFormat) -> bool {
+        match (&self.player_2, &self.result, format) {
+            (None, _, _) => true,
+            (_, Some(GameResult::Winner(_)), _) => true,
+            (_, Some(GameResult::Draw), TournamentFormat::RoundRobin) => true,
+            _ => false,
+        }
+    }
+
+    fn winner(&self) -> Option<Address> {
+        match (&self.player_2, &self.result) {
+            (None, _) => Some(self.player_1.clone()),
+            (_, Some(GameResult::Winner(winner))) => Some(winner.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Standing {
+    pub player: Address,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct Bracket {
+    pub tournament: Tournament,
+    /// The players fixed at the start, or the ones registered so far before that
+    pub players: Vec<Address>,
+    pub started: bool,
+    pub rounds: Vec<Vec<Pairing>>,
+    /// Index into rounds of the round being played, None before the start or once it is over
+    pub current_round: Option<usize>,
+    pub standings: Vec<Standing>,
+    pub winner: Option<Address>,
+}
+
+/// A reported game along with its result
+struct ReportedGame {
+    address: Address,
+    game: Game,
+    result: GameResult,
+}
+
+pub fn handle_create_tournament(
+    name: String,
+    format: TournamentFormat,
+    player_cap: usize,
+    created_at: u32,
+) -> ZomeApiResult<Address> {
+    let tournament = Tournament {
+        organiser: AGENT_ADDRESS.to_string().into(),
+        name,
+        format,
+        player_cap,
+        created_at,
+    };
+    hdk::commit_entry(&Entry::App("tournament".into(), tournament.into()))
+}
+
+pub fn handle_register(tournament_address: Address) -> ZomeApiResult<()> {
+    let tournament: Tournament = utils::get_as_type(tournament_address.clone())?;
+    let players = get_players(&tournament_address)?;
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    if players.contains(&me) {
+        return Ok(());
+    }
+    if get_start(&tournament_address)?.is_some() {
+        return Err(ZomeApiError::Internal(
+            "This tournament has already started".into(),
+        ));
+    }
+    if players.len() >= tournament.player_cap {
+        return Err(ZomeApiError::Internal("This tournament is full".into()));
+    }
+    let registration = Registration {
+        tournament: tournament_address.clone(),
+        player: me,
+    };
+    let registration_address =
+        hdk::commit_entry(&Entry::App("registration".into(), registration.into()))?;
+    hdk::link_entries(&tournament_address, &registration_address, "registered", "")?;
+    Ok(())
+}
+
+/// Close registration and fix the players, and with them the pairings. Players that registered
+/// at the same time as the last places were taken are left out, in address order like the seeding.
+pub fn handle_start_tournament(
+    tournament_address: Address,
+    started_at: u32,
+) -> ZomeApiResult<Address> {
+    let tournament: Tournament = utils::get_as_type(tournament_address.clone())?;
+    let me: Address = AGENT_ADDRESS.to_string().into();
+    if tournament.organiser != me {
+        return Err(ZomeApiError::Internal(
+            "Only the organiser can start a tournament".into(),
+        ));
+    }
+    if get_start(&tournament_address)?.is_some() {
+        return Err(ZomeApiError::Internal(
+            "This tournament has already started".into(),
+        ));
+    }
+    let mut players = get_players(&tournament_address)?;
+    players.sort_by_key(|player| player.to_string());
+    players.truncate(tournament.player_cap);
+    if players.len() < 2 {
+        return Err(ZomeApiError::Internal(
+            "A tournament needs two players to start".into(),
+        ));
+    }
+    let start = TournamentStart {
+        tournament: tournament_address.clone(),
+        players,
+        started_at,
+    };
+    let start_address = hdk::commit_entry(&Entry::App("tournament_start".into(), start.into()))?;
+    hdk::link_entries(&tournament_address, &start_address, "started", "")?;
+    Ok(start_address)
+}
+
+/// Report a finished game for an open pairing of the current round. Only the players and start of the game
+/// are validated, the rest is checked here against what this agent can see of the tournament.
+pub fn handle_report_result(
+    tournament_address: Address,
+    game_address: Address,
+) -> ZomeApiResult<()> {
+    let bracket = handle_get_bracket(tournament_address.clone())?;
+    let start = get_start(&tournament_address)?
+        .ok_or_else(|| ZomeApiError::Internal("This tournament has not started".into()))?;
+    let game = game::get_game(&game_address)?;
+    if game::get_state(&game_address)?.result(&game).is_none() {
+        return Err(ZomeApiError::Internal(
+            "Only finished games can be reported".into(),
+        ));
+    }
+    if game.created_at < start.entry.started_at {
+        return Err(ZomeApiError::Internal(
+            "Only games started after the tournament can be reported".into(),
+        ));
+    }
+    if get_reported_games(&start.address)?
+        .iter()
+        .any(|reported| reported.address == game_address)
+    {
+        return Err(ZomeApiError::Internal(
+            "This game has already been reported".into(),
+        ));
+    }
+    let current_round = bracket
+        .current_round
+        .ok_or_else(|| ZomeApiError::Internal("This tournament is not running".into()))?;
+    let pairing_open = bracket.rounds[current_round].iter().any(|pairing| {
+        pairing.is_between(&game) && !pairing.is_resolved(&bracket.tournament.format)
+    });
+    if !pairing_open {
+        return Err(ZomeApiError::Internal(
+            "This game is not for an open pairing in the current round".into(),
+        ));
+    }
+    hdk::link_entries(&start.address, &game_address, "has_result", "")?;
+    Ok(())
+}
+
+pub fn handle_get_bracket(tournament_address: Address) -> ZomeApiResult<Bracket> {
+    let tournament: Tournament = utils::get_as_type(tournament_address.clone())?;
+    match get_start(&tournament_address)? {
+        Some(start) => {
+            let reported = get_reported_games(&start.address)?;
+            Ok(build_bracket(
+                tournament,
+                start.entry.players,
+                true,
+                &reported,
+            ))
+        }
+        None => {
+            let mut players = get_players(&tournament_address)?;
+            players.sort_by_key(|player| player.to_string());
+            Ok(build_bracket(tournament, players, false, &[]))
+        }
+    }
+}
+
+fn get_players(tournament_address: &Address) -> ZomeApiResult<Vec<Address>> {
+    Ok(utils::get_links_and_load_type(
+        tournament_address,
+        LinkMatch::Exactly("registered"),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .map(|registration: Registration| registration.player)
+    .collect())
+}
+
+/// The start of a tournament, None before the organiser has started it. There is only more than one if the
+/// organiser started it twice at the same time, in which case everyone uses the earliest.
+fn get_start(tournament_address: &Address) -> ZomeApiResult<Option<GetResponse<TournamentStart>>> {
+    let mut starts: Vec<GetResponse<TournamentStart>> = utils::get_links_and_load_type(
+        tournament_address,
+        LinkMatch::Exactly("started"),
+        LinkMatch::Any,
+    )?
+    .into_iter()
+    .map(|start: TournamentStart| {
+        let address = Entry::App("tournament_start".into(), start.clone().into()).address();
+        GetResponse {
+            entry: start,
+            address,
+        }
+    })
+    .collect();
+    starts.sort_by_key(|start| (start.entry.started_at, start.address.to_string()));
+    Ok(starts.into_iter().next())
+}
+
+fn get_reported_games(start_address: &Address) -> ZomeApiResult<Vec<ReportedGame>> {
+    let mut reported: Vec<ReportedGame> = Vec::new();
+    for address in hdk::get_links(
+        start_address,
+        LinkMatch::Exactly("has_result"),
+        LinkMatch::Any,
+    )?
+    .addresses()
+    {
+        if reported
+            .iter()
+            .any(|reported_game| reported_game.address == address)
+        {
+            continue;
+        }
+        let game = game::get_game(&address)?;
+        // a game reported before it was over (as far as we can see) does not count until it is
+        if let Some(result) = game::get_state(&address)?.result(&game) {
+            reported.push(ReportedGame {
+                address,
+                game,
+                result,
+            });
+        }
+    }
+    // oldest games first so replays of a drawn elimination game are applied in order
+    reported.sort_by_key(|reported_game| reported_game.game.created_at);
+    Ok(reported)
+}
+
+/// Fill in the game and result of each pairing from the reported games. The first decisive game
+/// between two players settles a pairing, otherwise the first draw does.
+fn apply_results(pairings: &mut Vec<Pairing>, reported: &[ReportedGame]) {
+    for pairing in pairings.iter_mut() {
+        let games: Vec<&ReportedGame> = reported
+            .iter()
+            .filter(|reported_game| pairing.is_between(&reported_game.game))
+            .collect();
+        let decisive = games
+            .iter()
+            .find(|reported_game| match reported_game.result {
+                GameResult::Winner(_) => true,
+                _ => false,
+            });
+        if let Some(reported_game) = decisive.or_else(|| games.first()) {
+            pairing.game = Some(reported_game.address.clone());
+            pairing.result = Some(reported_game.result.clone());
+        }
+    }
+}
+
+fn pairing(player_1: Option<Address>, player_2: Option<Address>) -> Option<Pairing> {
+    match (player_1, player_2) {
+        (Some(player_1), player_2) => Some(Pairing {
+            player_1,
+            player_2,
+            game: None,
+            result: None,
+        }),
+        (None, Some(player_2)) => Some(Pairing {
+            player_1: player_2,
+            player_2: None,
+            game: None,
+            result: None,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Every player plays every other player once, scheduled with the circle method
+fn round_robin_rounds(players: &[Address]) -> Vec<Vec<Pairing>> {
+    let mut circle: Vec<Option<Address>> = players.iter().cloned().map(Some).collect();
+    if circle.len() % 2 == 1 {
+        circle.push(None);
+    }
+    let n = circle.len();
+    let mut rounds = Vec::new();
+    for _ in 1..n {
+        rounds.push(
+            (0..n / 2)
+                .filter_map(|i| pairing(circle[i].clone(), circle[n - 1 - i].clone()))
+                .collect(),
+        );
+        // keep the first player fixed and rotate everyone else one place
+        let last = circle.pop().unwrap();
+        circle.insert(1, last);
+    }
+    rounds
+}
+
+/// The first round of a knockout bracket. Seeds are the sorted player order, the top seeds get byes
+/// when the number of players is not a power of two. Pairings are laid out so the top two seeds can only meet in the final.
+fn elimination_first_round(players: &[Address]) -> Vec<Pairing> {
+    let size = players.len().next_power_of_two();
+    let mut seeds = vec![0];
+    while seeds.len() < size {
+        let opponent_of = seeds.len() * 2 - 1;
+        seeds = seeds
+            .into_iter()
+            .flat_map(|seed| vec![seed, opponent_of - seed])
+            .collect();
+    }
+    seeds
+        .chunks(2)
+        .filter_map(|pair| pairing(players.get(pair[0]).cloned(), players.get(pair[1]).cloned()))
+        .collect()
+}
+
+fn build_bracket(
+    tournament: Tournament,
+    players: Vec<Address>,
+    started: bool,
+    reported: &[ReportedGame],
+) -> Bracket {
+    let mut rounds = Vec::new();
+    if started {
+        match tournament.format {
+            TournamentFormat::RoundRobin => {
+                for mut round in round_robin_rounds(&players) {
+                    apply_results(&mut round, reported);
+                    rounds.push(round);
+                }
+            }
+            TournamentFormat::SingleElimination => {
+                let mut round = elimination_first_round(&players);
+                loop {
+                    apply_results(&mut round, reported);
+                    let resolved = round
+                        .iter()
+                        .all(|pairing| pairing.is_resolved(&tournament.format));
+                    let winners: Vec<Address> = round
+                        .iter()
+                        .filter_map(|pairing| pairing.winner())
+                        .collect();
+                    rounds.push(round);
+                    // later rounds are only known once everyone in this one has a winner
+                    if !resolved || winners.len() < 2 {
+                        break;
+                    }
+                    round = winners
+                        .chunks(2)
+                        .filter_map(|pair| pairing(pair.get(0).cloned(), pair.get(1).cloned()))
+                        .collect();
+                }
+            }
+        }
+    }
+
+    let current_round = rounds.iter().position(|round| {
+        round
+            .iter()
+            .any(|pairing| !pairing.is_resolved(&tournament.format))
+    });
+
+    let mut standings: Vec<Standing> = players
+        .iter()
+        .map(|player| Standing {
+            player: player.clone(),
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        })
+        .collect();
+    for pairing in rounds.iter().flatten() {
+        for standing in standings.iter_mut() {
+            let plays_in = standing.player == pairing.player_1
+                || pairing.player_2.as_ref() == Some(&standing.player);
+            match (&pairing.result, plays_in) {
+                (Some(GameResult::Winner(winner)), true) => {
+                    if *winner == standing.player {
+                        standing.wins += 1
+                    } else {
+                        standing.losses += 1
+                    }
+                }
+                (Some(GameResult::Draw), true) => standing.draws += 1,
+                _ => {}
+            }
+        }
+    }
+
+    let winner = if current_round.is_some() || rounds.is_empty() {
+        None
+    } else {
+        match tournament.format {
+            TournamentFormat::SingleElimination => rounds
+                .last()
+                .and_then(|round| round.first())
+                .and_then(|pairing| pairing.winner()),
+            // two points for a win and one for a draw, ties go to the earlier player in the sorted order
+            TournamentFormat::RoundRobin => standings
+                .iter()
+                .rev()
+                .max_by_key(|standing| standing.wins * 2 + standing.draws)
+                .map(|standing| standing.player.clone()),
+        }
+    };
+
+    Bracket {
+        tournament,
+        players,
+        started,
+        rounds,
+        current_round,
+        standings,
+        winner,
+    }
+}
+
+pub fn tournament_def() -> ValidatingEntryType {
+    entry!(
+        name: "tournament",
+        description: "A tournament players can register for, made up of ordinary games",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<Tournament>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let tournament = Tournament::from(entry);
+                    if !validation_data.sources().contains(&tournament.organiser) {
+                        return Err("Cannot organise a tournament for another agent".into());
+                    }
+                    if tournament.player_cap < 2 {
+                        return Err("A tournament needs room for at least two players".into());
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a tournament".into())
+                }
+            }
+        },
+        links: [
+            to!(
+                "registration",
+                link_type: "registered",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{ link, validation_data } => {
+                            let registration: Registration = utils::get_as_type(link.link.target().clone())?;
+                            if registration.tournament != *link.link.base() {
+                                return Err("A registration can only be linked from its own tournament".into());
+                            }
+                            if !validation_data.sources().contains(&registration.player) {
+                                return Err("Players can only register themselves".into());
+                            }
+                            // nothing here says whether the tournament is full or has started, register checks that.
+                            // Registering late changes nothing as the pairings only come from the players in the start
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot unregister from a tournament".into())
+                        }
+                    }
+                }
+            ),
+            to!(
+                "tournament_start",
+                link_type: "started",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{ link, .. } => {
+                            let start: TournamentStart = utils::get_as_type(link.link.target().clone())?;
+                            if start.tournament != *link.link.base() {
+                                return Err("A start can only be linked from its own tournament".into());
+                            }
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot undo the start of a tournament".into())
+                        }
+                    }
+                }
+            )
+        ]
+    )
+}
+
+pub fn registration_def() -> ValidatingEntryType {
+    entry!(
+        name: "registration",
+        description: "A player signing up to play in a tournament",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<Registration>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let registration = Registration::from(entry);
+                    if !validation_data.sources().contains(&registration.player) {
+                        return Err("Players can only register themselves".into());
+                    }
+                    let _tournament: Tournament = utils::get_as_type(registration.tournament)?;
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete a registration".into())
+                }
+            }
+        }
+    )
+}
+
+pub fn tournament_start_def() -> ValidatingEntryType {
+    entry!(
+        name: "tournament_start",
+        description: "The organiser closing registration for a tournament and fixing its players",
+        sharing: Sharing::Public,
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+        validation: | validation_data: hdk::EntryValidationData<TournamentStart>| {
+            match validation_data {
+                EntryValidationData::Create{ entry, validation_data } => {
+                    let start = TournamentStart::from(entry);
+                    let tournament: Tournament = utils::get_as_type(start.tournament.clone())?;
+                    if !validation_data.sources().contains(&tournament.organiser) {
+                        return Err("Only the organiser can start a tournament".into());
+                    }
+                    if start.players.len() < 2 || start.players.len() > tournament.player_cap {
+                        return Err(format!("A tournament starts with between 2 and {} players", tournament.player_cap));
+                    }
+                    if start.started_at < tournament.created_at {
+                        return Err("A tournament cannot start before it was created".into());
+                    }
+                    // sorted without repeats, so everyone works out the same pairings
+                    if start.players.windows(2).any(|pair| pair[0].to_string() >= pair[1].to_string()) {
+                        return Err("The players of a tournament must be sorted by address".into());
+                    }
+                    for player in start.players.iter() {
+                        let registration = Registration {
+                            tournament: start.tournament.clone(),
+                            player: player.clone(),
+                        };
+                        let registration_address = Entry::App("registration".into(), registration.into()).address();
+                        if hdk::get_entry(&registration_address)?.is_none() {
+                            return Err("Only players that registered can be in a tournament".into());
+                        }
+                    }
+                    Ok(())
+                },
+                _ => {
+                    Err("Cannot modify or delete the start of a tournament".into())
+                }
+            }
+        },
+        links: [
+            to!(
+                "game",
+                link_type: "has_result",
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+                validation: | validation_data: hdk::LinkValidationData| {
+                    match validation_data {
+                        LinkValidationData::LinkAdd{ link, .. } => {
+                            let start: TournamentStart = utils::get_as_type(link.link.base().clone())?;
+                            let game: Game = utils::get_as_type(link.link.target().clone())?;
+                            if !start.players.contains(&game.player_1) || !start.players.contains(&game.player_2) {
+                                return Err("Only games between players of the tournament can be reported".into());
+                            }
+                            if game.created_at < start.started_at {
+                                return Err("Only games started after the tournament can be reported".into());
+                            }
+                            // whether the game is over and was reported before depends on what has reached the
+                            // validator, so report_result checks those and the bracket ignores unfinished and repeated reports
+                            Ok(())
+                        },
+                        LinkValidationData::LinkRemove{..} => {
+                            Err("Cannot remove a reported result".into())
+                        }
+                    }
+                }
+            )
+        ]
+    )
+}